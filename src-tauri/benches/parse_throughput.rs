@@ -0,0 +1,112 @@
+//! Files parsed per second, one file at a time and on all cores.
+//!
+//! Runs on a generated project by default, set `JDG_BENCH_DIR` to measure a real one:
+//! `JDG_BENCH_DIR=~/src/monorepo cargo bench --bench parse_throughput`
+
+use std::path::{Path, PathBuf};
+
+use criterion::{criterion_group, criterion_main, Criterion, Throughput};
+use java_diagram_generator_lib::java_parser::{parse_java_file, parse_java_files};
+
+const GENERATED_FILES: usize = 500;
+
+/// Generated sources, removed again when the bench is done
+struct GeneratedProject {
+    dir: PathBuf,
+    files: Vec<String>,
+}
+
+impl Drop for GeneratedProject {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_dir_all(&self.dir);
+    }
+}
+
+/// Write a project of classes with fields, methods and references to each other
+fn generate_project() -> GeneratedProject {
+    let dir = std::env::temp_dir().join(format!("jdg-bench-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let files = (0..GENERATED_FILES)
+        .map(|i| {
+            let path = dir.join(format!("Service{}.java", i));
+            let source = format!(
+                r#"package com.example.bench;
+
+import java.util.List;
+import java.util.Map;
+
+public class Service{i}{extends} implements Comparable<Service{i}> {{
+    private final List<Service{next}> children;
+    private Map<String, Service{parent}> byName;
+    protected static int count;
+
+    public Service{i}(List<Service{next}> children) {{
+        this.children = children;
+    }}
+
+    public Service{next} first() {{ return children.get(0); }}
+
+    @Override
+    public int compareTo(Service{i} other) {{ return 0; }}
+
+    enum State {{ IDLE, RUNNING }}
+}}
+"#,
+                i = i,
+                // Each service extends the one before, the first extends nothing
+                extends = match i {
+                    0 => String::new(),
+                    _ => format!(" extends Service{}", i - 1),
+                },
+                parent = i.saturating_sub(1),
+                next = (i + 1) % GENERATED_FILES,
+            );
+            std::fs::write(&path, source).unwrap();
+            path.to_string_lossy().into_owned()
+        })
+        .collect();
+    GeneratedProject { dir, files }
+}
+
+fn collect_java_files(dir: &Path, files: &mut Vec<String>) {
+    for entry in std::fs::read_dir(dir).unwrap() {
+        let path = entry.unwrap().path();
+        if path.is_dir() {
+            collect_java_files(&path, files);
+        } else if path
+            .extension()
+            .is_some_and(|extension| extension == "java")
+        {
+            files.push(path.to_string_lossy().into_owned());
+        }
+    }
+}
+
+fn parse_throughput(c: &mut Criterion) {
+    // Kept until the end of the bench, which deletes the generated files
+    let mut generated = None;
+    let files = match std::env::var("JDG_BENCH_DIR") {
+        Ok(dir) => {
+            let mut files = Vec::new();
+            collect_java_files(Path::new(&dir), &mut files);
+            files
+        }
+        Err(_) => generated.insert(generate_project()).files.clone(),
+    };
+
+    let mut group = c.benchmark_group("parse");
+    group.throughput(Throughput::Elements(files.len() as u64));
+    group.sample_size(10);
+    group.bench_function("sequential", |b| {
+        b.iter(|| {
+            for file in &files {
+                let _ = parse_java_file(file);
+            }
+        })
+    });
+    group.bench_function("parallel", |b| b.iter(|| parse_java_files(&files, None)));
+    group.finish();
+}
+
+criterion_group!(benches, parse_throughput);
+criterion_main!(benches);
@@ -1,10 +1,18 @@
+use std::collections::HashSet;
 use std::time;
 
-use log::info;
+use log::{info, warn};
+use tauri::AppHandle;
 
-use crate::diagram_generator::generate_diagram;
-use crate::java_parser::parse_java_file;
-use crate::types::{FileNode, Project};
+use crate::diagram_generator::{build_diagram, node_locations, to_mermaid, DiagramOptions};
+use crate::java_parser::parse_java_files;
+use crate::neighborhood::{neighborhood, NeighborhoodOptions};
+use crate::project_watcher::watch_project;
+use crate::symbol_index::{project_index, Symbol, SymbolIndex};
+use crate::type_resolver::resolve_types;
+use crate::types::{
+    Config, FileNode, GeneratedDiagram, ParsedFile, Project, Settings, SourceLocation,
+};
 
 #[tauri::command]
 pub fn greet(name: &str) -> String {
@@ -12,9 +20,12 @@ pub fn greet(name: &str) -> String {
     format!("Hello, {}! You've been greeted from Rust!", name)
 }
 
+/// Folders left out of the file tree and ignored by the project watcher
+pub const IGNORED_FOLDERS: [&str; 5] = ["node_modules", "target", "build", "dist", ".git"];
+
 // read file structure from a path
 #[tauri::command]
-pub fn read_file_structure(id: &str) -> FileNode {
+pub fn read_file_structure(app: AppHandle, id: &str) -> FileNode {
     let config = parse_config();
     let mut mut_config = config.clone();
     let mut path = "";
@@ -26,6 +37,10 @@ pub fn read_file_structure(id: &str) -> FileNode {
     for project in config.iter() {
         if project.id.to_string() == id {
             path = &project.path;
+            // keep the tree, parse cache and symbol index up to date while the project is open
+            if let Err(e) = watch_project(app, project.clone()) {
+                warn!("Failed to watch {}: {}", project.path, e);
+            }
             break;
         }
     }
@@ -45,47 +60,79 @@ pub fn read_file_structure(id: &str) -> FileNode {
             children: Vec::new(),
         };
     }
-    return read_structure_from_path(path).unwrap();
+    let structure = match read_structure_from_path(path) {
+        Ok(structure) => structure,
+        Err(e) => {
+            warn!("Failed to read {}: {}", path, e);
+            None
+        }
+    };
+    structure.unwrap_or_else(|| FileNode {
+        name: "".to_string(),
+        path: path.to_string(),
+        r#type: "".to_string(),
+        children: Vec::new(),
+    })
 }
 
 fn write_config(config: Vec<Project>) {
-    let json_string = serde_json::to_string(&config).unwrap();
+    let config = Config {
+        projects: config,
+        settings: read_config().settings,
+    };
+    save_config(&config);
+}
+
+fn save_config(config: &Config) {
+    let json_string = serde_json::to_string(config).unwrap();
     // println!("{}", json_string);
     std::fs::write(ensure_config_exists(), json_string).unwrap();
 }
 
-pub fn read_structure_from_path(path: &str) -> Option<FileNode> {
+/// The Java files below `path` and the folders containing any. Entries removed while the walk
+/// is under way are left out, other IO errors end it.
+pub fn read_structure_from_path(path: &str) -> std::io::Result<Option<FileNode>> {
     let path = std::path::Path::new(path);
     let mut root = FileNode {
-        name: path.file_name().unwrap().to_str().unwrap().to_string(),
-        path: path.to_str().unwrap().to_string(),
+        name: path
+            .file_name()
+            .unwrap_or_default()
+            .to_string_lossy()
+            .to_string(),
+        path: path.to_string_lossy().to_string(),
         r#type: if path.is_dir() { "folder" } else { "file" }.to_string(),
         children: Vec::new(),
     };
     if !path.is_dir() {
         if path.extension().unwrap_or_default() == "java" {
-            return Some(root);
+            return Ok(Some(root));
         } else {
-            return None;
+            return Ok(None);
         }
     }
-    for entry in path.read_dir().unwrap() {
+    let entries = match path.read_dir() {
+        Ok(entries) => entries,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(None),
+        Err(e) => return Err(e),
+    };
+    for entry in entries {
+        let entry = match entry {
+            Ok(entry) => entry,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => continue,
+            Err(e) => return Err(e),
+        };
         // ignore some common folders
-        const IGNORE: [&str; 5] = ["node_modules", "target", "build", "dist", ".git"];
-        let entry = entry.unwrap();
-        if IGNORE.contains(&entry.file_name().to_str().unwrap()) {
+        if IGNORED_FOLDERS.contains(&entry.file_name().to_string_lossy().as_ref()) {
             continue;
         }
-        let node = read_structure_from_path(&entry.path().to_str().unwrap());
-        if node.is_none() {
-            continue;
+        if let Some(node) = read_structure_from_path(&entry.path().to_string_lossy())? {
+            root.children.push(node);
         }
-        root.children.push(node.unwrap());
     }
-    if root.children.len() == 0 {
-        return None;
+    if root.children.is_empty() {
+        return Ok(None);
     }
-    return Some(root);
+    Ok(Some(root))
 }
 
 #[allow(deprecated)]
@@ -106,14 +153,85 @@ pub fn ensure_config_exists() -> String {
     return json_path;
 }
 
+// the project the files belong to, the one whose folder contains them
+fn project_for_files(file_paths: &[String]) -> Option<Project> {
+    let first = std::path::Path::new(file_paths.first()?);
+    parse_config()
+        .into_iter()
+        .filter(|project| first.starts_with(&project.path))
+        .max_by_key(|project| project.path.len())
+}
+
+pub fn project_cache_path(project: &Project) -> std::path::PathBuf {
+    let config_path = ensure_config_exists();
+    let config_dir = std::path::Path::new(&config_path).parent().unwrap();
+    config_dir
+        .join("cache")
+        .join(format!("{}.json", project.id))
+}
+
+// every java file of a project, skipping the same folders as the file tree
+fn project_java_files(project: &Project) -> Vec<String> {
+    fn collect(node: &FileNode, files: &mut Vec<String>) {
+        if node.r#type == "file" {
+            files.push(node.path.to_owned());
+        }
+        for child in &node.children {
+            collect(child, files);
+        }
+    }
+    let mut files = Vec::new();
+    match read_structure_from_path(&project.path) {
+        Ok(Some(root)) => collect(&root, &mut files),
+        Ok(None) => {}
+        Err(e) => warn!("Failed to read {}: {}", project.path, e),
+    }
+    files
+}
+
+// every class of a project, parsed through its cache
+fn parse_project(project: &Project) -> ParsedFile {
+    let files = project_java_files(project);
+    parse_java_files(&files, Some(&project_cache_path(project)))
+}
+
+// symbol index of a project, parsing the whole project the first time
+fn project_symbols(project: &Project) -> std::sync::Arc<SymbolIndex> {
+    project_index(project.id, || {
+        SymbolIndex::build(&parse_project(project).classes)
+    })
+}
+
+fn find_project(id: &str) -> Result<Project, String> {
+    parse_config()
+        .into_iter()
+        .find(|project| project.id.to_string() == id)
+        .ok_or(format!("Project {} not found", id))
+}
+
 pub fn parse_config() -> Vec<Project> {
+    read_config().projects
+}
+
+fn config_from_json(json_string: &str) -> Result<Config, serde_json::Error> {
+    let json_string = json_string.trim();
+    if json_string.is_empty() {
+        return Ok(Config::default());
+    }
+    // the config file used to hold only the list of projects
+    if json_string.starts_with('[') {
+        return Ok(Config {
+            projects: serde_json::from_str(json_string)?,
+            settings: Settings::default(),
+        });
+    }
+    serde_json::from_str(json_string)
+}
+
+pub fn read_config() -> Config {
     let json_path = ensure_config_exists();
     let json_string = std::fs::read_to_string(json_path).unwrap();
-    if json_string == "" {
-        return Vec::new();
-    }
-    let projects: Vec<Project> = serde_json::from_str(&json_string).unwrap();
-    return projects;
+    config_from_json(&json_string).unwrap()
 }
 
 #[tauri::command]
@@ -133,8 +251,7 @@ pub fn add_new_project(id: &str, name: &str, path: &str, last_opened: &str) -> S
     }
     config.push(new_project);
 
-    let json_string = serde_json::to_string(&config).unwrap();
-    std::fs::write(ensure_config_exists(), json_string).unwrap();
+    write_config(config);
     return "Success".to_string();
 }
 
@@ -148,16 +265,157 @@ pub fn get_projects() -> Vec<Project> {
 pub async fn generate_mermaid_class_diagram(
     file_paths: Vec<String>,
     vertical: bool,
-) -> Result<String, String> {
-    let mut classes = Vec::new();
-
-    for path in file_paths {
-        // println!("Parsing {}", path);
-        match parse_java_file(&path) {
-            Ok(class_info) => classes.push(class_info),
-            Err(e) => return Err(format!("Failed to parse {}: {}", path, e)),
-        }
+    options: Option<DiagramOptions>,
+) -> GeneratedDiagram {
+    // A file that can't be read or parsed is reported instead of failing the whole diagram
+    let project = project_for_files(&file_paths);
+    let cache_path = project.as_ref().map(project_cache_path);
+    let mut parsed = parse_java_files(&file_paths, cache_path.as_deref());
+    // Types that aren't selected still resolve, for ghosts and wildcard imports
+    let project_types = match &project {
+        Some(project) => project_symbols(project).type_names(),
+        None => HashSet::new(),
+    };
+    resolve_types(&mut parsed.classes, &project_types);
+
+    let options = options.unwrap_or_default();
+    let diagram = build_diagram(parsed.classes, &options);
+    GeneratedDiagram {
+        mermaid: to_mermaid(&diagram, vertical, &options),
+        locations: node_locations(&diagram),
+        relationships: diagram.relationships,
+        diagnostics: parsed.diagnostics,
     }
+}
+
+// diagram of a class and the project classes it reaches within `depth` relationships
+#[tauri::command]
+pub async fn generate_neighborhood_diagram(
+    id: String,
+    class_name: String, // qualified name of the seed class
+    depth: usize,
+    vertical: bool,
+    relationships: Option<NeighborhoodOptions>,
+    options: Option<DiagramOptions>,
+) -> Result<GeneratedDiagram, String> {
+    let project = find_project(&id)?;
+    let mut parsed = parse_project(&project);
+    // The classes are the whole project already
+    resolve_types(&mut parsed.classes, &HashSet::new());
 
-    Ok(generate_diagram(classes, vertical))
+    let options = options.unwrap_or_default();
+    let relationships = relationships.unwrap_or_default();
+    let classes = neighborhood(
+        &parsed.classes,
+        &class_name,
+        depth,
+        &relationships,
+        &options,
+    )
+    .ok_or(format!("Class {} not found", class_name))?;
+    let files = classes
+        .iter()
+        .map(|class| class.location.file.as_str())
+        .collect::<std::collections::HashSet<_>>();
+    let diagnostics = parsed
+        .diagnostics
+        .into_iter()
+        .filter(|diagnostic| files.contains(diagnostic.file.as_str()))
+        .collect();
+
+    let diagram = build_diagram(classes, &options);
+    Ok(GeneratedDiagram {
+        mermaid: to_mermaid(&diagram, vertical, &options),
+        locations: node_locations(&diagram),
+        relationships: diagram.relationships,
+        diagnostics,
+    })
+}
+
+// open the source of a diagram element in the editor of the settings
+#[tauri::command]
+pub fn open_in_editor(location: SourceLocation) -> Result<(), String> {
+    let editor = read_config().settings.editor;
+    let spawned = std::process::Command::new(&editor.program)
+        .args(editor.arguments(&location))
+        .spawn();
+    match spawned {
+        Ok(_) => Ok(()),
+        Err(e) => Err(format!("Failed to run {}: {}", editor.program, e)),
+    }
+}
+
+#[tauri::command]
+pub fn get_settings() -> Settings {
+    read_config().settings
+}
+
+#[tauri::command]
+pub fn save_settings(settings: Settings) -> Result<(), String> {
+    if settings.editor.program.trim().is_empty() {
+        return Err("The editor program is empty".to_string());
+    }
+    let mut config = read_config();
+    config.settings = settings;
+    save_config(&config);
+    Ok(())
+}
+
+// fuzzy search over the packages, types and members of a project, "OSvc" finds OrderService
+#[tauri::command]
+pub async fn search_symbols(
+    id: String,
+    query: String,
+    limit: Option<usize>,
+) -> Result<Vec<Symbol>, String> {
+    let project = find_project(&id)?;
+    // The whole project is parsed once, later searches reuse the index
+    let index = project_symbols(&project);
+    Ok(index.search(&query, limit.unwrap_or(50)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::config_from_json;
+    use crate::types::{EditorCommand, SourceLocation};
+
+    #[test]
+    fn reads_the_project_list_of_older_configs() {
+        let projects = r#"[{"id":1,"name":"shop","path":"/shop","last_opened":0}]"#;
+        let config = config_from_json(projects).unwrap();
+        assert_eq!(config.projects[0].name, "shop");
+        assert_eq!(config.settings.editor, EditorCommand::default());
+
+        let current =
+            r#"{"projects":[],"settings":{"editor":{"program":"idea","args":["{file}"]}}}"#;
+        let config = config_from_json(current).unwrap();
+        assert_eq!(config.settings.editor.program, "idea");
+        assert!(config_from_json("").unwrap().projects.is_empty());
+    }
+
+    #[test]
+    fn editor_arguments_keep_paths_whole() {
+        let location = SourceLocation {
+            file: String::from("/my projects/Order.java"),
+            start_line: 12,
+            start_column: 5,
+            ..Default::default()
+        };
+        let editor = EditorCommand {
+            program: String::from("idea"),
+            args: vec![
+                String::from("--line"),
+                String::from("{line}"),
+                String::from("{file}"),
+            ],
+        };
+        assert_eq!(
+            editor.arguments(&location),
+            ["--line", "12", "/my projects/Order.java"]
+        );
+        assert_eq!(
+            EditorCommand::default().arguments(&location),
+            ["--goto", "/my projects/Order.java:12:5"]
+        );
+    }
 }
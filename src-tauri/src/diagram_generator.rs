@@ -1,130 +1,1263 @@
+use std::collections::{HashMap, HashSet};
+
 use serde::{Deserialize, Serialize};
 
-use crate::types::{ClassInfo, ClassType};
+use crate::types::{
+    ClassField, ClassInfo, ClassType, FieldValue, MemberLocation, Modifier, NodeLocation,
+    Parameter, SourceLocation, TypeArgument, TypeRef, Visibility,
+};
 
 #[derive(Debug, Serialize, Deserialize)]
 pub struct ClassDiagram {
     pub classes: Vec<ClassInfo>,
     pub relationships: Vec<Relationship>,
+    pub ghosts: Vec<String>, // relationship ends outside the diagram drawn as placeholders
 }
 
 #[derive(Debug, Serialize, Deserialize)]
 pub struct Relationship {
-    pub from: String,
+    pub from: String, // qualified name, or the simple name of an unresolved type
     pub to: String,
-    pub type_: String, // "extends", "implements", "association", "composition", "aggregation"
+    pub kind: RelationshipKind,
+    pub label: Option<String>, // field names, several when fields of one type were merged
+    pub multiplicity: Option<String>, // cardinality on the `to` side, e.g. "1", "0..1", "*"
+    pub from_multiplicity: Option<String>, // cardinality on the `from` side of a two-way link
+    pub bidirectional: bool,   // an association both classes hold a field for
+    pub location: Option<SourceLocation>, // declaration the relationship comes from
+    pub reason: Option<String>, // why a field is a composition or an aggregation
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq)]
+pub enum RelationshipKind {
+    Extends,    // `from` is a subclass of `to`
+    Implements, // `from` implements the interface `to`
+    Association,
+    Composition, // `from` owns `to`
+    Aggregation, // `from` holds a `to` it was given
+    Dependency,  // `from` uses `to` in its methods or constructors
+    Nested,      // `to` is declared inside `from`
+}
+
+/// Heuristics telling a field's composition or aggregation apart from a plain association,
+/// the first one that applies wins
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq)]
+pub enum OwnershipRule {
+    CreatedFinalField,   // composition: a final field the class fills with `new`
+    Embedded,            // composition: JPA `@Embedded`, `@EmbeddedId` or `@ElementCollection`
+    CascadeAll,          // composition: a JPA relationship with `cascade = ALL` or orphan removal
+    ConstructorInjected, // aggregation: a field set from a constructor parameter
+    SetterInjected,      // aggregation: a field set from a method parameter
+}
+
+impl OwnershipRule {
+    fn kind(self) -> RelationshipKind {
+        match self {
+            OwnershipRule::CreatedFinalField
+            | OwnershipRule::Embedded
+            | OwnershipRule::CascadeAll => RelationshipKind::Composition,
+            OwnershipRule::ConstructorInjected | OwnershipRule::SetterInjected => {
+                RelationshipKind::Aggregation
+            }
+        }
+    }
+}
+
+/// Options that change how classes and relationships end up in the diagram
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(default)]
+pub struct DiagramOptions {
+    // Wrapper types whose type arguments are the associated classes, by qualified name
+    pub collection_types: Vec<String>, // many elements, first type argument
+    pub map_types: Vec<String>,        // many values, last type argument
+    pub optional_types: Vec<String>,   // at most one element, first type argument
+    pub show_record_accessors: bool,   // list the implicit accessor of each record component
+    pub show_annotations: bool,        // render type annotations as stereotypes, e.g. <<Entity>>
+    pub show_dependencies: bool,       // `..>` to the types methods and constructors use
+    // Render referenced types that aren't part of the diagram as empty, faded placeholder
+    // classes, and keep the field associations to them
+    pub show_ghost_types: bool,
+    pub ghost_ignored_packages: Vec<String>, // package prefixes never turned into ghosts
+    pub ownership_rules: Vec<OwnershipRule>,
+    pub group_by_package: bool, // put classes into a Mermaid namespace per package
+    // Package prefix left out of namespace names, by default the one all classes share
+    pub namespace_prefix: Option<String>,
+    // Package segments after the prefix that make up a namespace, deeper packages are cut
+    // to that many and share the namespace. 0 keeps every segment.
+    pub namespace_depth: usize,
+}
+
+impl Default for DiagramOptions {
+    fn default() -> Self {
+        let to_strings = |names: &[&str]| names.iter().map(|name| name.to_string()).collect();
+        DiagramOptions {
+            collection_types: to_strings(&[
+                // JDK
+                "java.lang.Iterable",
+                "java.util.Collection",
+                "java.util.List",
+                "java.util.ArrayList",
+                "java.util.LinkedList",
+                "java.util.concurrent.CopyOnWriteArrayList",
+                "java.util.Set",
+                "java.util.HashSet",
+                "java.util.LinkedHashSet",
+                "java.util.TreeSet",
+                "java.util.SortedSet",
+                "java.util.NavigableSet",
+                "java.util.EnumSet",
+                "java.util.Queue",
+                "java.util.Deque",
+                "java.util.ArrayDeque",
+                "java.util.PriorityQueue",
+                "java.util.concurrent.BlockingQueue",
+                "java.util.stream.Stream",
+                // Guava
+                "com.google.common.collect.ImmutableCollection",
+                "com.google.common.collect.ImmutableList",
+                "com.google.common.collect.ImmutableSet",
+                "com.google.common.collect.ImmutableSortedSet",
+                "com.google.common.collect.Multiset",
+                "com.google.common.collect.HashMultiset",
+                "com.google.common.collect.ImmutableMultiset",
+                // Vavr
+                "io.vavr.collection.Traversable",
+                "io.vavr.collection.Seq",
+                "io.vavr.collection.IndexedSeq",
+                "io.vavr.collection.List",
+                "io.vavr.collection.Vector",
+                "io.vavr.collection.Array",
+                "io.vavr.collection.Set",
+                "io.vavr.collection.HashSet",
+                "io.vavr.collection.TreeSet",
+            ]),
+            map_types: to_strings(&[
+                // JDK
+                "java.util.Map",
+                "java.util.HashMap",
+                "java.util.LinkedHashMap",
+                "java.util.TreeMap",
+                "java.util.SortedMap",
+                "java.util.NavigableMap",
+                "java.util.EnumMap",
+                "java.util.WeakHashMap",
+                "java.util.concurrent.ConcurrentMap",
+                "java.util.concurrent.ConcurrentHashMap",
+                // Guava
+                "com.google.common.collect.ImmutableMap",
+                "com.google.common.collect.ImmutableSortedMap",
+                "com.google.common.collect.BiMap",
+                "com.google.common.collect.HashBiMap",
+                "com.google.common.collect.Multimap",
+                "com.google.common.collect.ListMultimap",
+                "com.google.common.collect.SetMultimap",
+                "com.google.common.collect.ArrayListMultimap",
+                "com.google.common.collect.HashMultimap",
+                // Vavr
+                "io.vavr.collection.Map",
+                "io.vavr.collection.HashMap",
+                "io.vavr.collection.LinkedHashMap",
+                "io.vavr.collection.TreeMap",
+            ]),
+            optional_types: to_strings(&[
+                // JDK
+                "java.util.Optional",
+                "java.util.concurrent.atomic.AtomicReference",
+                // Guava
+                "com.google.common.base.Optional",
+                // Vavr
+                "io.vavr.control.Option",
+                "io.vavr.Lazy",
+            ]),
+            show_record_accessors: false,
+            show_annotations: false,
+            show_dependencies: false,
+            show_ghost_types: false,
+            // Strings, dates and collections would crowd out the types worth showing
+            ghost_ignored_packages: to_strings(&["java."]),
+            ownership_rules: vec![
+                OwnershipRule::CreatedFinalField,
+                OwnershipRule::Embedded,
+                OwnershipRule::CascadeAll,
+                OwnershipRule::ConstructorInjected,
+                OwnershipRule::SetterInjected,
+            ],
+            group_by_package: false,
+            namespace_prefix: None,
+            namespace_depth: 0,
+        }
+    }
 }
 
-pub fn generate_diagram(classes: Vec<ClassInfo>, vertical: bool) -> String {
+/// Style of the placeholder classes drawn for types outside the diagram
+const GHOST_STYLE: &str = "classDef ghost fill:none,stroke:#999,stroke-dasharray:4 4,color:#888\n";
+
+/// Find the relationships between the classes, the type references have to be resolved
+pub fn build_diagram(classes: Vec<ClassInfo>, options: &DiagramOptions) -> ClassDiagram {
     let mut diagram = ClassDiagram {
         classes,
         relationships: Vec::new(),
+        ghosts: Vec::new(),
     };
 
     // Process inheritance relationships
     for class in &diagram.classes {
+        let qualified_name = class.qualified_name();
         if let Some(extends) = &class.extends {
+            add_ghost(&mut diagram.ghosts, &diagram.classes, extends, options);
             diagram.relationships.push(Relationship {
-                from: class.name.to_owned(),
-                to: extends.to_owned(),
-                type_: String::from("extends"),
+                from: qualified_name.clone(),
+                to: type_key(extends),
+                kind: RelationshipKind::Extends,
+                label: None,
+                multiplicity: None,
+                from_multiplicity: None,
+                bidirectional: false,
+                location: Some(class.location.clone()),
+                reason: None,
             });
         }
 
         for implements in &class.implements {
+            add_ghost(&mut diagram.ghosts, &diagram.classes, implements, options);
+            diagram.relationships.push(Relationship {
+                from: qualified_name.clone(),
+                to: type_key(implements),
+                kind: RelationshipKind::Implements,
+                label: None,
+                multiplicity: None,
+                from_multiplicity: None,
+                bidirectional: false,
+                location: Some(class.location.clone()),
+                reason: None,
+            });
+        }
+
+        // Permitted subtypes get an edge even when they aren't part of the diagram
+        for permitted in &class.permits {
+            let declared = find_class(&diagram.classes, permitted).is_some_and(|c| {
+                c.extends
+                    .iter()
+                    .chain(&c.implements)
+                    .any(|t| type_key(t) == qualified_name)
+            });
+            if declared {
+                continue;
+            }
+            add_ghost(&mut diagram.ghosts, &diagram.classes, permitted, options);
+            let kind = match class.class_type {
+                ClassType::Interface => RelationshipKind::Implements,
+                _ => RelationshipKind::Extends,
+            };
+            diagram.relationships.push(Relationship {
+                from: type_key(permitted),
+                to: qualified_name.clone(),
+                kind,
+                label: Some(String::from("permits")),
+                multiplicity: None,
+                from_multiplicity: None,
+                bidirectional: false,
+                location: Some(class.location.clone()),
+                reason: None,
+            });
+        }
+
+        if let Some(outer) = &class.outer {
+            let outer = match class.package.as_str() {
+                "" => outer.to_owned(),
+                package => format!("{}.{}", package, outer),
+            };
             diagram.relationships.push(Relationship {
-                from: class.name.to_owned(),
-                to: implements.to_owned(),
-                type_: String::from("implements"),
+                from: outer,
+                to: qualified_name.clone(),
+                kind: RelationshipKind::Nested,
+                label: None,
+                multiplicity: None,
+                from_multiplicity: None,
+                bidirectional: false,
+                location: Some(class.location.clone()),
+                reason: None,
             });
         }
     }
 
-    // Process associations based on field types
+    // Process associations based on field types, looking through containers and wrappers
     for class in &diagram.classes {
         for field in &class.fields {
-            if diagram.classes.iter().any(|c| c.name == field.type_name) {
-                diagram.relationships.push(Relationship {
-                    from: class.name.to_owned(),
-                    to: field.type_name.to_owned(),
-                    type_: String::from("association"),
-                });
+            let (element, multiplicity) = association_target(&field.type_ref, options);
+            if element.primitive {
+                continue;
+            }
+            let target = match find_class(&diagram.classes, element) {
+                Some(target) => target.qualified_name(),
+                None if is_ghost(element, options) => {
+                    add_ghost(&mut diagram.ghosts, &diagram.classes, element, options);
+                    type_key(element)
+                }
+                None => continue,
+            };
+            let (kind, reason) = match ownership(field, options) {
+                Some((kind, reason)) => (kind, Some(reason)),
+                None => (RelationshipKind::Association, None),
+            };
+            diagram.relationships.push(Relationship {
+                from: class.qualified_name(),
+                to: target,
+                kind,
+                label: Some(field.name.to_owned()),
+                multiplicity: Some(multiplicity.to_string()),
+                from_multiplicity: None,
+                bidirectional: false,
+                location: Some(field.location.clone()),
+                reason,
+            });
+        }
+    }
+
+    // Process dependencies on the types methods and constructors use, once per class and only
+    // where no field or supertype relates the classes already
+    if options.show_dependencies {
+        let mut dependencies = Vec::new();
+        for class in &diagram.classes {
+            let from = class.qualified_name();
+            let mut targets: Vec<(String, &SourceLocation)> = Vec::new();
+            for (type_ref, location) in dependency_types(class) {
+                let to = match find_class(&diagram.classes, type_ref) {
+                    Some(target) => target.qualified_name(),
+                    None if is_ghost(type_ref, options) => type_key(type_ref),
+                    None => continue,
+                };
+                let related = to == from
+                    || targets.iter().any(|(target, _)| *target == to)
+                    || diagram
+                        .relationships
+                        .iter()
+                        .any(|rel| rel.from == from && rel.to == to);
+                if !related {
+                    add_ghost(&mut diagram.ghosts, &diagram.classes, type_ref, options);
+                    targets.push((to, location));
+                }
+            }
+            dependencies.extend(targets.into_iter().map(|(to, location)| Relationship {
+                from: from.clone(),
+                to,
+                kind: RelationshipKind::Dependency,
+                label: None,
+                multiplicity: None,
+                from_multiplicity: None,
+                bidirectional: false,
+                location: Some(location.clone()),
+                reason: None,
+            }));
+        }
+        diagram.relationships.extend(dependencies);
+    }
+    diagram.relationships = merge_relationships(diagram.relationships);
+    diagram
+}
+
+/// Merge edges of the same kind between the same classes, e.g. two fields of one type, and
+/// turn associations both ways between two classes into one two-way association
+fn merge_relationships(relationships: Vec<Relationship>) -> Vec<Relationship> {
+    let mut merged: Vec<Relationship> = Vec::new();
+    for rel in relationships {
+        let same = merged
+            .iter_mut()
+            .find(|m| m.from == rel.from && m.to == rel.to && m.kind == rel.kind);
+        let same = match same {
+            Some(same) => same,
+            None => {
+                merged.push(rel);
+                continue;
             }
+        };
+        same.label = join(same.label.take(), rel.label, ", ");
+        same.reason = join(same.reason.take(), rel.reason, "; ");
+        if let (Some(a), Some(b)) = (&same.multiplicity, &rel.multiplicity) {
+            same.multiplicity = Some(merge_multiplicity(a, b).to_string());
         }
     }
-    to_mermaid(&diagram, vertical)
+
+    let mut collapsed: Vec<Relationship> = Vec::new();
+    for rel in merged {
+        let reverse = collapsed.iter_mut().find(|c| {
+            c.kind == RelationshipKind::Association
+                && rel.kind == RelationshipKind::Association
+                && !c.bidirectional
+                && c.from == rel.to
+                && c.to == rel.from
+                && c.from != c.to
+        });
+        match reverse {
+            Some(reverse) => {
+                reverse.bidirectional = true;
+                reverse.from_multiplicity = rel.multiplicity;
+                reverse.label = join(reverse.label.take(), rel.label, " / ");
+            }
+            None => collapsed.push(rel),
+        }
+    }
+    collapsed
+}
+
+fn join(a: Option<String>, b: Option<String>, separator: &str) -> Option<String> {
+    match (a, b) {
+        (Some(a), Some(b)) if a == b => Some(a),
+        (Some(a), Some(b)) => Some(format!("{}{}{}", a, separator, b)),
+        (a, b) => a.or(b),
+    }
+}
+
+/// Cardinality covering both of the merged ones, "1" and "0..1" make "0..1"
+fn merge_multiplicity(a: &str, b: &str) -> &'static str {
+    match (a, b) {
+        ("1", "1") => "1",
+        ("*", _) | (_, "*") => "*",
+        _ => "0..1",
+    }
+}
+
+/// Whether a field is a composition or an aggregation according to the first rule that
+/// applies, and the reason
+fn ownership(field: &ClassField, options: &DiagramOptions) -> Option<(RelationshipKind, String)> {
+    let annotation = |names: &[&str]| {
+        field.annotations.iter().find(|annotation| {
+            let name = annotation.name.as_str();
+            names.contains(&name.rsplit('.').next().unwrap_or(name))
+        })
+    };
+    for rule in &options.ownership_rules {
+        let reason = match rule {
+            OwnershipRule::CreatedFinalField => {
+                let created = field.values.contains(&FieldValue::Created);
+                (created && field.modifiers.contains(&Modifier::Final))
+                    .then(|| String::from("final field created with new"))
+            }
+            OwnershipRule::Embedded => annotation(&["Embedded", "EmbeddedId", "ElementCollection"])
+                .map(|embedded| format!("@{}", embedded.name)),
+            OwnershipRule::CascadeAll => annotation(&["OneToOne", "OneToMany", "ManyToMany"])
+                .and_then(|relationship| {
+                    let value = |name: &str| {
+                        let mut elements = relationship.elements.iter();
+                        let element = elements.find(|element| element.name == name);
+                        element.map(|element| element.value.as_str())
+                    };
+                    // `CascadeType.ALL`, `ALL` when imported statically, or inside `{...}`
+                    let cascade_all = value("cascade").is_some_and(|cascade| {
+                        let mut parts = cascade.split(|c: char| !c.is_alphanumeric() && c != '_');
+                        parts.any(|part| part == "ALL")
+                    });
+                    if cascade_all {
+                        Some(format!("@{} with cascade = ALL", relationship.name))
+                    } else if value("orphanRemoval") == Some("true") {
+                        Some(format!("@{} with orphan removal", relationship.name))
+                    } else {
+                        None
+                    }
+                }),
+            OwnershipRule::ConstructorInjected => field
+                .values
+                .contains(&FieldValue::ConstructorParameter)
+                .then(|| String::from("set from a constructor parameter")),
+            OwnershipRule::SetterInjected => field
+                .values
+                .contains(&FieldValue::MethodParameter)
+                .then(|| String::from("set from a method parameter")),
+        };
+        if let Some(reason) = reason {
+            return Some((rule.kind(), reason));
+        }
+    }
+    None
+}
+
+/// Every type named in the signatures and bodies of a class's methods and constructors,
+/// type arguments included, with the member that names it
+pub fn dependency_types(class: &ClassInfo) -> Vec<(&TypeRef, &SourceLocation)> {
+    let mut types = Vec::new();
+    let constant_methods = class.enum_constants.iter().flat_map(|c| &c.methods);
+    for method in class.methods.iter().chain(constant_methods) {
+        let parameters = method.parameters.iter().map(|p| &p.type_ref);
+        let signature = std::iter::once(&method.return_type).chain(parameters);
+        for type_ref in signature.chain(&method.throws).chain(&method.used_types) {
+            collect_types(type_ref, &method.location, &mut types);
+        }
+    }
+    for constructor in &class.constructors {
+        let parameters = constructor.parameters.iter().map(|p| &p.type_ref);
+        for type_ref in parameters
+            .chain(&constructor.throws)
+            .chain(&constructor.used_types)
+        {
+            collect_types(type_ref, &constructor.location, &mut types);
+        }
+    }
+    types
+}
+
+/// A type and, recursively, its type arguments: `Map<String, List<Order>>` names `Order`
+fn collect_types<'a>(
+    type_ref: &'a TypeRef,
+    location: &'a SourceLocation,
+    types: &mut Vec<(&'a TypeRef, &'a SourceLocation)>,
+) {
+    if type_ref.primitive {
+        return;
+    }
+    types.push((type_ref, location));
+    for argument in &type_ref.arguments {
+        match argument {
+            TypeArgument::Type(argument)
+            | TypeArgument::Extends(argument)
+            | TypeArgument::Super(argument) => collect_types(argument, location, types),
+            TypeArgument::Wildcard => {}
+        }
+    }
+}
+
+/// Unwrap arrays, collections, maps and optionals down to the associated element type,
+/// along with the multiplicity the wrappers imply. A project class that happens to be called
+/// `Vector` or `Option` resolves to its own qualified name and isn't unwrapped.
+pub fn association_target<'a>(
+    type_ref: &'a TypeRef,
+    options: &DiagramOptions,
+) -> (&'a TypeRef, &'static str) {
+    let contains = |types: &[String]| match &type_ref.qualified_name {
+        Some(qualified_name) => types.contains(qualified_name),
+        // No project type has the name, a wildcard import brought it in from a library
+        None => types
+            .iter()
+            .any(|t| t.rsplit('.').next() == Some(type_ref.name.as_str())),
+    };
+    let (argument, multiplicity) = if type_ref.array_dimensions > 0 {
+        return (type_ref, "*");
+    } else if contains(&options.collection_types) {
+        (type_ref.arguments.first(), "*")
+    } else if contains(&options.map_types) {
+        (type_ref.arguments.last(), "*")
+    } else if contains(&options.optional_types) {
+        (type_ref.arguments.first(), "0..1")
+    } else {
+        return (type_ref, "1");
+    };
+
+    let element = match argument {
+        Some(TypeArgument::Type(element))
+        | Some(TypeArgument::Extends(element))
+        | Some(TypeArgument::Super(element)) => element,
+        // Raw or wildcard containers say nothing about their elements
+        Some(TypeArgument::Wildcard) | None => return (type_ref, multiplicity),
+    };
+    let (element, inner_multiplicity) = association_target(element, options);
+    match (multiplicity, inner_multiplicity) {
+        ("*", _) | (_, "*") => (element, "*"),
+        _ => (element, "0..1"),
+    }
+}
+
+/// Whether a resolved type outside the diagram is shown as a ghost. Unresolved references
+/// are left out, they are mostly type variables.
+fn is_ghost(type_ref: &TypeRef, options: &DiagramOptions) -> bool {
+    let qualified_name = match &type_ref.qualified_name {
+        Some(qualified_name) if options.show_ghost_types => qualified_name,
+        _ => return false,
+    };
+    !options
+        .ghost_ignored_packages
+        .iter()
+        .any(|package| qualified_name.starts_with(package))
+}
+
+/// Remember a type outside the diagram that is drawn as a ghost, once
+fn add_ghost(
+    ghosts: &mut Vec<String>,
+    classes: &[ClassInfo],
+    type_ref: &TypeRef,
+    options: &DiagramOptions,
+) {
+    let key = type_key(type_ref);
+    if find_class(classes, type_ref).is_none()
+        && is_ghost(type_ref, options)
+        && !ghosts.contains(&key)
+    {
+        ghosts.push(key);
+    }
+}
+
+/// Relationship end for a type reference, unresolved types fall back to their simple name
+fn type_key(type_ref: &TypeRef) -> String {
+    match &type_ref.qualified_name {
+        Some(qualified_name) => qualified_name.to_owned(),
+        None => type_ref.name.to_owned(),
+    }
+}
+
+/// Find the diagram class a type reference resolves to. Every diagram class is known to
+/// the resolver, so unresolved references such as type variables never match one.
+fn find_class<'a>(classes: &'a [ClassInfo], type_ref: &TypeRef) -> Option<&'a ClassInfo> {
+    let qualified_name = type_ref.qualified_name.as_ref()?;
+    classes
+        .iter()
+        .find(|c| &c.qualified_name() == qualified_name)
+}
+
+/// Name each class and relationship end is shown with: its simple name, or its qualified
+/// name when any other of them shares the simple name
+fn display_names(diagram: &ClassDiagram) -> HashMap<String, String> {
+    let mut simple_names = diagram
+        .classes
+        .iter()
+        .map(|class| (class.qualified_name(), class.name.to_owned()))
+        .collect::<HashMap<_, _>>();
+    for rel in &diagram.relationships {
+        for key in [&rel.from, &rel.to] {
+            if !simple_names.contains_key(key) {
+                let simple_name = key.rsplit('.').next().unwrap_or(key);
+                simple_names.insert(key.to_owned(), simple_name.to_string());
+            }
+        }
+    }
+
+    let mut counts = HashMap::new();
+    for simple_name in simple_names.values() {
+        *counts.entry(simple_name.as_str()).or_insert(0) += 1;
+    }
+    simple_names
+        .iter()
+        .map(|(key, simple_name)| {
+            let name = if counts[simple_name.as_str()] > 1 {
+                key.to_owned()
+            } else {
+                simple_name.to_owned()
+            };
+            (key.to_owned(), name)
+        })
+        .collect()
+}
+
+/// Source locations of the classes and members of a diagram, by the name each class is
+/// shown with
+pub fn node_locations(diagram: &ClassDiagram) -> HashMap<String, NodeLocation> {
+    let names = display_names(diagram);
+    diagram
+        .classes
+        .iter()
+        .map(|class| {
+            let member = |name: &str, location: &SourceLocation| MemberLocation {
+                name: name.to_string(),
+                location: location.clone(),
+            };
+            let simple_name = class.name.rsplit('.').next().unwrap_or(&class.name);
+            let mut members = Vec::new();
+            for constant in &class.enum_constants {
+                members.push(member(&constant.name, &constant.location));
+            }
+            for field in &class.fields {
+                members.push(member(&field.name, &field.location));
+            }
+            for constructor in &class.constructors {
+                members.push(member(simple_name, &constructor.location));
+            }
+            for method in &class.methods {
+                members.push(member(&method.name, &method.location));
+            }
+            let location = NodeLocation {
+                class: class.location.clone(),
+                members,
+            };
+            (names[&class.qualified_name()].clone(), location)
+        })
+        .collect()
 }
 
-pub fn to_mermaid(diagram: &ClassDiagram, vertical: bool) -> String {
+pub fn to_mermaid(diagram: &ClassDiagram, vertical: bool, options: &DiagramOptions) -> String {
     let mut mermaid = String::from("classDiagram\n");
     if vertical {
         mermaid.push_str("direction LR\n");
     }
 
-    // Add classes with their members
+    let names = display_names(diagram);
+
+    // Add classes with their members, grouped by namespace in the order they first appear
+    let prefix = match &options.namespace_prefix {
+        _ if !options.group_by_package => None,
+        Some(prefix) => Some(prefix.to_owned()),
+        None => Some(common_package(&diagram.classes)),
+    };
+    let mut namespaces: Vec<(Option<String>, String)> = Vec::new();
     for class in &diagram.classes {
+        let mut definition = String::new();
         // Class declaration
         let kind = match class.class_type {
-            ClassType::Interface => "    <<interface>>\n",
-            ClassType::Enum => "    <<enumeration>>\n",
-            _ => "",
+            ClassType::Interface => Some("interface"),
+            ClassType::Enum => Some("enumeration"),
+            ClassType::Annotation => Some("annotation"),
+            _ => None,
+        };
+        let kind = match (class.modifiers.contains(&Modifier::Sealed), kind) {
+            (true, Some(kind)) => Some(format!("sealed {}", kind)),
+            (true, None) => Some(String::from("sealed")),
+            (false, kind) => kind.map(String::from),
         };
-        mermaid.push_str(&format!("class {} {{\n", class.name));
-        mermaid.push_str(&format!("{}", kind)); // Class head
+        // Mermaid only shows the first stereotype of a class, so everything shares one
+        let mut stereotypes = kind.into_iter().collect::<Vec<_>>();
+        if options.show_annotations {
+            stereotypes.extend(class.annotations.iter().map(|annotation| {
+                let simple_name = annotation.name.rsplit('.').next();
+                simple_name.unwrap_or(&annotation.name).to_string()
+            }));
+        }
+        let stereotype = if stereotypes.is_empty() {
+            String::new()
+        } else {
+            format!("    <<{}>>\n", stereotypes.join(", "))
+        };
+        // Mermaid writes generics as Box~T~, bounds have no place in the syntax
+        let generics = if class.type_parameters.is_empty() {
+            String::new()
+        } else {
+            let names = class
+                .type_parameters
+                .iter()
+                .map(|type_parameter| type_parameter.name.as_str())
+                .collect::<Vec<_>>()
+                .join(", ");
+            format!("~{}~", names)
+        };
+        definition.push_str(&format!(
+            "class {}{} {{\n",
+            mermaid_name(&names[&class.qualified_name()]),
+            generics
+        ));
+        definition.push_str(&stereotype); // Class head
+
+        // Enum constants, listed before and apart from the fields
+        for constant in &class.enum_constants {
+            definition.push_str(&format!("    {}\n", constant.name));
+        }
 
         // Fields
         for field in &class.fields {
-            let field_visibility = match field.visibility.as_str() {
-                "public" => "+",
-                "private" => "-",
-                "protected" => "#",
-                _ => "",
-            };
-            mermaid.push_str(&format!(
-                "    {} {}: {}\n",
-                field_visibility, field.name, field.type_name
+            definition.push_str(&format!(
+                "    {} {}: {}{}\n",
+                visibility_marker(field.visibility),
+                field.name,
+                field.type_ref,
+                classifier(&field.modifiers)
+            ));
+        }
+
+        // Constructors, named after the class like in the source
+        let simple_name = class.name.rsplit('.').next().unwrap_or(&class.name);
+        for constructor in &class.constructors {
+            definition.push_str(&format!(
+                "    {} {}({})\n",
+                visibility_marker(constructor.visibility),
+                simple_name,
+                format_parameters(&constructor.parameters)
             ));
         }
 
         // Methods
         for method in &class.methods {
-            let params = method
-                .parameters
-                .iter()
-                .map(|(name, type_)| format!("{}: {}", name, type_))
-                .collect::<Vec<_>>()
-                .join(", ");
+            let params = format_parameters(&method.parameters);
 
-            mermaid.push_str(&format!(
-                "    {} {}: {}({})\n",
-                match method.visibility.as_str() {
-                    "public" => "+",
-                    "private" => "-",
-                    "protected" => "#",
-                    _ => "",
-                },
+            definition.push_str(&format!(
+                "    {} {}: {}({}){}\n",
+                visibility_marker(method.visibility),
                 method.name,
                 method.return_type,
-                params
+                params,
+                classifier(&method.modifiers)
             ));
         }
 
-        mermaid.push_str("}\n");
+        // Record accessors that aren't declared explicitly
+        if options.show_record_accessors && matches!(class.class_type, ClassType::Record) {
+            let components = class
+                .fields
+                .iter()
+                .filter(|field| !field.modifiers.contains(&Modifier::Static));
+            for component in components {
+                let declared = class
+                    .methods
+                    .iter()
+                    .any(|method| method.name == component.name && method.parameters.is_empty());
+                if !declared {
+                    definition.push_str(&format!(
+                        "    + {}: {}()\n",
+                        component.name, component.type_ref
+                    ));
+                }
+            }
+        }
+
+        definition.push_str("}\n");
+
+        let namespace = match &prefix {
+            Some(prefix) => namespace(&class.package, prefix, options.namespace_depth),
+            None => None,
+        };
+        match namespaces.iter_mut().find(|(name, _)| *name == namespace) {
+            Some((_, definitions)) => definitions.push_str(&definition),
+            None => namespaces.push((namespace, definition)),
+        }
+    }
+    // Classes outside any namespace first
+    namespaces.sort_by_key(|(name, _)| name.is_some());
+    for (name, definitions) in namespaces {
+        match name {
+            Some(name) => {
+                // Unlike class names, namespace names take dots as they are
+                mermaid.push_str(&format!("namespace {} {{\n", name));
+                for line in definitions.lines() {
+                    mermaid.push_str(&format!("    {}\n", line));
+                }
+                mermaid.push_str("}\n");
+            }
+            None => mermaid.push_str(&definitions),
+        }
+    }
+
+    // Mermaid would draw the ghosts as plain classes
+    if !diagram.ghosts.is_empty() {
+        mermaid.push_str(GHOST_STYLE);
+    }
+    for key in &diagram.ghosts {
+        let name = mermaid_name(&names[key]);
+        mermaid.push_str(&format!("class {}:::ghost\n", name));
     }
 
     // Add relationships
     for rel in &diagram.relationships {
-        let arrow = match rel.type_.as_str() {
-            "extends" => "<|--",
-            "implements" => "<|--",
-            "association" => "-->",
-            "composition" => "*--",
-            "aggregation" => "o--",
-            _ => "--",
+        // The arrow points from `from` to `to`, so supertypes are at the head
+        let arrow = match rel.kind {
+            RelationshipKind::Extends => "--|>",
+            RelationshipKind::Implements => "..|>",
+            RelationshipKind::Association if rel.bidirectional => "<-->",
+            RelationshipKind::Association => "-->",
+            RelationshipKind::Composition => "*--",
+            RelationshipKind::Aggregation => "o--",
+            RelationshipKind::Dependency => "..>",
+            RelationshipKind::Nested => "..",
         };
 
-        mermaid.push_str(&format!("{} {} {}\n", rel.from, arrow, rel.to));
+        let label = match &rel.label {
+            Some(label) => format!(" : {}", label),
+            None => String::new(),
+        };
+        match &rel.multiplicity {
+            Some(multiplicity) => mermaid.push_str(&format!(
+                "{} \"{}\" {} \"{}\" {}{}\n",
+                mermaid_name(&names[&rel.from]),
+                rel.from_multiplicity.as_deref().unwrap_or("1"),
+                arrow,
+                multiplicity,
+                mermaid_name(&names[&rel.to]),
+                label
+            )),
+            None => mermaid.push_str(&format!(
+                "{} {} {}{}\n",
+                mermaid_name(&names[&rel.from]),
+                arrow,
+                mermaid_name(&names[&rel.to]),
+                label
+            )),
+        }
     }
 
     mermaid
 }
+
+/// Namespace of the classes of a package: the package without `prefix`, cut to `depth`
+/// segments. None for the prefix itself.
+fn namespace(package: &str, prefix: &str, depth: usize) -> Option<String> {
+    let trimmed = match package.strip_prefix(prefix) {
+        Some(rest) if prefix.is_empty() => rest,
+        Some(rest) if rest.is_empty() || rest.starts_with('.') => &rest[rest.len().min(1)..],
+        _ => package, // outside the prefix, keep the whole package
+    };
+    let segments = trimmed.split('.').filter(|segment| !segment.is_empty());
+    let segments = match depth {
+        0 => segments.collect::<Vec<_>>(),
+        depth => segments.take(depth).collect(),
+    };
+    if segments.is_empty() {
+        None
+    } else {
+        Some(segments.join("."))
+    }
+}
+
+/// The longest package every class is in, e.g. `com.shop` for `com.shop.domain` and
+/// `com.shop.web`. Empty when there's only one package, which then makes up the namespace.
+fn common_package(classes: &[ClassInfo]) -> String {
+    // Classes of the default package are drawn outside the namespaces anyway
+    let packages = classes
+        .iter()
+        .map(|class| class.package.as_str())
+        .filter(|package| !package.is_empty())
+        .collect::<HashSet<_>>();
+    if packages.len() < 2 {
+        return String::new();
+    }
+    let mut packages = packages.into_iter().map(|package| package.split('.'));
+    let mut common = match packages.next() {
+        Some(first) => first.collect::<Vec<_>>(),
+        None => return String::new(),
+    };
+    for package in packages {
+        let shared = common
+            .iter()
+            .zip(package)
+            .take_while(|(a, b)| **a == *b)
+            .count();
+        common.truncate(shared);
+    }
+    common.join(".")
+}
+
+fn format_parameters(parameters: &[Parameter]) -> String {
+    parameters
+        .iter()
+        .map(|parameter| format!("{}: {}", parameter.name, parameter.type_ref))
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+fn visibility_marker(visibility: Visibility) -> &'static str {
+    match visibility {
+        Visibility::Public => "+",
+        Visibility::Protected => "#",
+        Visibility::PackagePrivate => "~",
+        Visibility::Private => "-",
+    }
+}
+
+/// Mermaid classifier suffix: `$` for static members, `*` for abstract ones
+fn classifier(modifiers: &[Modifier]) -> &'static str {
+    if modifiers.contains(&Modifier::Static) {
+        "$"
+    } else if modifiers.contains(&Modifier::Abstract) {
+        "*"
+    } else {
+        ""
+    }
+}
+
+/// Mermaid only accepts dotted names such as `Outer.Inner` when they are wrapped in backticks.
+fn mermaid_name(name: &str) -> String {
+    if name.contains('.') {
+        format!("`{}`", name)
+    } else {
+        name.to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::java_parser::parse_java_source;
+    use crate::type_resolver::resolve_types;
+
+    fn mermaid(sources: &[&str], options: &DiagramOptions) -> String {
+        let mut classes = sources
+            .iter()
+            .enumerate()
+            .flat_map(|(i, source_code)| {
+                let file_path = format!("File{}.java", i);
+                parse_java_source(&file_path, source_code).unwrap().classes
+            })
+            .collect::<Vec<_>>();
+        resolve_types(&mut classes, &HashSet::new());
+        to_mermaid(&build_diagram(classes, options), false, options)
+    }
+
+    fn relationship(from: &str, to: &str, kind: RelationshipKind, label: &str) -> Relationship {
+        Relationship {
+            from: from.to_string(),
+            to: to.to_string(),
+            kind,
+            label: Some(label.to_string()),
+            multiplicity: Some("1".to_string()),
+            from_multiplicity: None,
+            bidirectional: false,
+            location: None,
+            reason: None,
+        }
+    }
+
+    #[test]
+    fn merges_duplicate_edges() {
+        let mut many = relationship("a.Order", "a.Line", RelationshipKind::Association, "lines");
+        many.multiplicity = Some("*".to_string());
+        let merged = merge_relationships(vec![
+            relationship("a.Order", "a.Line", RelationshipKind::Association, "first"),
+            many,
+            relationship("a.Order", "a.Line", RelationshipKind::Dependency, "add"),
+        ]);
+        assert_eq!(merged.len(), 2);
+        assert_eq!(merged[0].label.as_deref(), Some("first, lines"));
+        assert_eq!(merged[0].multiplicity.as_deref(), Some("*"));
+        assert_eq!(merged[1].kind, RelationshipKind::Dependency);
+    }
+
+    #[test]
+    fn collapses_associations_both_ways() {
+        let mut back = relationship(
+            "a.Customer",
+            "a.Order",
+            RelationshipKind::Association,
+            "orders",
+        );
+        back.multiplicity = Some("*".to_string());
+        let merged = merge_relationships(vec![
+            relationship(
+                "a.Order",
+                "a.Customer",
+                RelationshipKind::Association,
+                "customer",
+            ),
+            back,
+            relationship("a.Node", "a.Node", RelationshipKind::Association, "next"),
+        ]);
+        assert_eq!(merged.len(), 2);
+        assert!(merged[0].bidirectional);
+        assert_eq!(merged[0].from_multiplicity.as_deref(), Some("*"));
+        assert_eq!(merged[0].label.as_deref(), Some("customer / orders"));
+        assert!(!merged[1].bidirectional);
+    }
+
+    #[test]
+    fn nested_types_hang_off_their_owner() {
+        let source_code = "package shop; class Order { static class Line {} enum State { OPEN } }";
+        let mermaid = mermaid(&[source_code], &DiagramOptions::default());
+        assert!(mermaid.contains("class `Order.Line` {"));
+        assert!(mermaid.contains("Order .. `Order.Line`\n"));
+        assert!(mermaid.contains("Order .. `Order.State`\n"));
+    }
+
+    #[test]
+    fn associations_through_containers() {
+        let source_code = "package shop;
+            import java.util.*;
+            import io.vavr.control.Option;
+            class Order {
+                List<Line> lines;
+                Map<String, Line[]> byName;
+                Option<Address> address;
+                Optional<List<Customer>> previous;
+                Vector<Line> vector;
+            }
+            class Line {}
+            class Customer {}
+            class Address {}
+            class Vector<T> {}";
+        let mermaid = mermaid(&[source_code], &DiagramOptions::default());
+        assert!(mermaid.contains("Order \"1\" --> \"*\" Line : lines, byName\n"));
+        assert!(mermaid.contains("Order \"1\" --> \"0..1\" Address : address\n"));
+        assert!(mermaid.contains("Order \"1\" --> \"*\" Customer : previous\n"));
+        // The project's own Vector is a class like any other
+        assert!(mermaid.contains("Order \"1\" --> \"1\" Vector : vector\n"));
+    }
+
+    #[test]
+    fn interfaces_realize_extended_interfaces() {
+        let source_code = "package pay;
+            interface PaymentGateway extends Gateway, Auditable<String> {}
+            interface Gateway {}
+            interface Auditable<T> {}
+            class StripeGateway implements PaymentGateway {}";
+        let mermaid = mermaid(&[source_code], &DiagramOptions::default());
+        assert!(mermaid.contains("PaymentGateway ..|> Gateway\n"));
+        assert!(mermaid.contains("PaymentGateway ..|> Auditable\n"));
+        assert!(mermaid.contains("StripeGateway ..|> PaymentGateway\n"));
+    }
+
+    #[test]
+    fn visibility_markers_and_classifiers() {
+        let source_code = "abstract class Job {
+            private static int count;
+            protected String name;
+            public abstract void run();
+            int retries() { return 0; }
+        }";
+        let mermaid = mermaid(&[source_code], &DiagramOptions::default());
+        assert!(mermaid.contains("    - count: int$\n"));
+        assert!(mermaid.contains("    # name: String\n"));
+        assert!(mermaid.contains("    + run: void()*\n"));
+        assert!(mermaid.contains("    ~ retries: int()\n"));
+    }
+
+    #[test]
+    fn constructors_are_named_after_the_class() {
+        let source_code =
+            "class Order { static class Line { Line(int count, String... notes) {} } }";
+        let mermaid = mermaid(&[source_code], &DiagramOptions::default());
+        assert!(mermaid.contains("    ~ Line(count: int, notes: String...)\n"));
+    }
+
+    #[test]
+    fn enum_constants_before_fields() {
+        let source_code = "package shop;
+            interface Priced {}
+            enum Currency implements Priced { EUR(\"€\"), USD(\"$\"); private final String symbol; }";
+        let mermaid = mermaid(&[source_code], &DiagramOptions::default());
+        assert!(mermaid.contains("    EUR\n    USD\n    - symbol: String\n"));
+        assert!(mermaid.contains("Currency ..|> Priced\n"));
+    }
+
+    #[test]
+    fn record_accessors_unless_declared() {
+        let source_code = "record Point(int x, int y) { public int y() { return y; } }";
+        let mut options = DiagramOptions::default();
+        assert!(!mermaid(&[source_code], &options).contains("+ x: int()"));
+        options.show_record_accessors = true;
+        let mermaid = mermaid(&[source_code], &options);
+        assert!(mermaid.contains("    + x: int()\n"));
+        assert_eq!(mermaid.matches(" y: int()").count(), 1);
+    }
+
+    #[test]
+    fn sealed_types_and_permitted_subtypes() {
+        let source_code = "package shapes;
+            sealed interface Shape permits Circle, Square, Polygon {}
+            final class Circle implements Shape {}
+            non-sealed class Square implements Shape {}
+            sealed abstract class Base permits Leaf {}
+            final class Leaf extends Base {}";
+        let classes = parse_java_source("Shape.java", source_code)
+            .unwrap()
+            .classes;
+        assert_eq!(classes[0].modifiers, [Modifier::Sealed]);
+        let permits = classes[0].permits.iter().map(|t| t.name.as_str());
+        assert_eq!(permits.collect::<Vec<_>>(), ["Circle", "Square", "Polygon"]);
+        assert_eq!(classes[2].modifiers, [Modifier::NonSealed]);
+
+        let mermaid = mermaid(&[source_code], &DiagramOptions::default());
+        assert!(mermaid.contains("    <<sealed interface>>\n"));
+        assert!(mermaid.contains("    <<sealed>>\n"));
+        // Declared supertypes aren't drawn twice, Polygon is only known from the clause
+        assert_eq!(mermaid.matches("Circle ..|> Shape").count(), 1);
+        assert!(mermaid.contains("Polygon ..|> Shape : permits\n"));
+        assert_eq!(mermaid.matches("Leaf --|> Base").count(), 1);
+    }
+
+    #[test]
+    fn dependencies_only_when_nothing_else_relates_the_classes() {
+        let source_code = "import java.util.List;
+            class Order {}
+            class Invoice {}
+            class Mailer {}
+            class Payment {}
+            class Checkout {
+                Order order;
+                Checkout(Payment payment) {}
+                Invoice bill(Order order, List<Mailer> mailers) { return new Invoice(); }
+                void merge(Checkout other) {}
+            }";
+        assert!(!mermaid(&[source_code], &DiagramOptions::default()).contains("..>"));
+
+        let options = DiagramOptions {
+            show_dependencies: true,
+            ..Default::default()
+        };
+        let mermaid = mermaid(&[source_code], &options);
+        let dependencies = mermaid.lines().filter(|line| line.contains("..>"));
+        // Once per type, Order is related by a field already and Checkout is the class itself
+        assert_eq!(
+            dependencies.collect::<Vec<_>>(),
+            [
+                "Checkout ..> Invoice",
+                "Checkout ..> Mailer",
+                "Checkout ..> Payment"
+            ]
+        );
+    }
+
+    #[test]
+    fn ghosts_for_project_types_outside_the_diagram() {
+        let source_code = "package a;
+            import java.util.*;
+            import b.*;
+            class Order extends Entity implements java.io.Serializable {
+                Customer customer;
+                List<Line> lines;
+            }";
+        // Entity is neither in the project nor imported by name, so there's no telling which it is
+        let project_types = HashSet::from(["a.Customer".to_string(), "b.Line".to_string()]);
+        let mut classes = parse_java_source("Order.java", source_code)
+            .unwrap()
+            .classes;
+        resolve_types(&mut classes, &project_types);
+        let options = DiagramOptions {
+            show_ghost_types: true,
+            ..Default::default()
+        };
+        let mermaid = to_mermaid(&build_diagram(classes, &options), false, &options);
+        assert!(mermaid.contains("class Customer:::ghost\n"));
+        assert!(mermaid.contains("class Line:::ghost\n"));
+        assert!(mermaid.contains("Order \"1\" --> \"*\" Line : lines\n"));
+        // Unresolved supertypes and those of ignored packages are drawn as plain classes
+        assert!(mermaid.contains("Order --|> Entity\n"));
+        assert!(mermaid.contains("Order ..|> Serializable\n"));
+        assert_eq!(mermaid.matches(":::ghost").count(), 2);
+    }
+
+    #[test]
+    fn namespaces_by_package() {
+        let sources = [
+            "package com.shop.domain; class Order {}",
+            "package com.shop.domain.model; class Line {}",
+            "package com.shop.web; class OrderController {}",
+            "package com.shop; class Application {}",
+        ];
+        let mut options = DiagramOptions {
+            group_by_package: true,
+            ..Default::default()
+        };
+        // The package all classes share is left out, its own classes stay outside
+        let mermaid_text = mermaid(&sources, &options);
+        assert!(mermaid_text.starts_with("classDiagram\nclass Application"));
+        assert!(mermaid_text.contains("namespace domain {\n    class Order"));
+        assert!(mermaid_text.contains("namespace domain.model {\n    class Line"));
+        assert!(mermaid_text.contains("namespace web {\n    class OrderController"));
+
+        // Deeper packages share the namespace of the package at that depth
+        options.namespace_depth = 1;
+        let mermaid_text = mermaid(&sources, &options);
+        assert!(
+            mermaid_text.contains("namespace domain {\n    class Order {\n    }\n    class Line")
+        );
+        assert_eq!(mermaid_text.matches("namespace ").count(), 2);
+
+        options.namespace_prefix = Some(String::new());
+        options.namespace_depth = 2;
+        let mermaid_text = mermaid(&sources, &options);
+        assert!(mermaid_text.contains("namespace com.shop {\n    class Order"));
+        assert!(mermaid_text.contains("    class Line"));
+        assert!(mermaid_text.contains("    class OrderController"));
+        assert_eq!(mermaid_text.matches("namespace ").count(), 1);
+    }
+
+    #[test]
+    fn single_package_keeps_its_namespace() {
+        let source_code = "package com.shop.domain; class Order {} class Line {}";
+        let options = DiagramOptions {
+            group_by_package: true,
+            ..Default::default()
+        };
+        let mermaid = mermaid(&[source_code], &options);
+        assert!(mermaid.contains("namespace com.shop.domain {\n    class Order"));
+        assert!(mermaid.contains("    class Line"));
+    }
+
+    #[test]
+    fn annotations_share_the_stereotype() {
+        let source_code = "@Deprecated @javax.persistence.Entity interface Order {}";
+        let mut options = DiagramOptions::default();
+        assert!(mermaid(&[source_code], &options).contains("    <<interface>>\n"));
+        options.show_annotations = true;
+        let mermaid = mermaid(&[source_code], &options);
+        assert!(mermaid.contains("    <<interface, Deprecated, Entity>>\n"));
+    }
+}
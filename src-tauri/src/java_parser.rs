@@ -1,54 +1,417 @@
 use std::fs;
-use tree_sitter::Parser;
+use std::path::Path;
 
-use crate::{parsers, types::ClassInfo};
+use rayon::prelude::*;
+use tree_sitter::{Node, Tree};
 
-pub fn parse_java_file(file_path: &str) -> Result<ClassInfo, Box<dyn std::error::Error>> {
+use crate::{
+    parse_cache::{content_hash, with_cache},
+    parser_pool::ParserPool,
+    parsers::{
+        self,
+        member_parser::{body_declarations, parse_annotations, parse_modifiers},
+        type_parser::parse_type_list,
+    },
+    types::{ClassInfo, Diagnostic, Parameter, ParsedFile, Severity},
+};
+
+const TYPE_DECLARATIONS: [&str; 5] = [
+    "class_declaration",
+    "interface_declaration",
+    "enum_declaration",
+    "record_declaration",
+    "annotation_type_declaration",
+];
+
+/// Parse every type declared in a Java file. Syntax errors don't stop the parse, they are
+/// reported as diagnostics next to whatever classes could still be recovered.
+pub fn parse_java_file(file_path: &str) -> Result<ParsedFile, Box<dyn std::error::Error>> {
     let source_code = fs::read_to_string(file_path)?;
+    parse_java_source(file_path, &source_code)
+}
 
-    // Initialize tree-sitter parser
-    let mut parser = Parser::new();
-    let language = tree_sitter_java::language();
-    parser.set_language(language)?;
+/// Parse the already read content of a Java file
+pub fn parse_java_source(
+    file_path: &str,
+    source_code: &str,
+) -> Result<ParsedFile, Box<dyn std::error::Error>> {
+    let (parsed, _) = parse_java_source_with(file_path, source_code, None)?;
+    Ok(parsed)
+}
 
-    let tree = parser.parse(&source_code, None).unwrap();
+/// Parse the content of a Java file, reusing the unchanged parts of `previous`: the tree of
+/// an earlier version of the file that has been told about the edit with `Tree::edit`
+pub fn parse_java_source_with(
+    file_path: &str,
+    source_code: &str,
+    previous: Option<&Tree>,
+) -> Result<(ParsedFile, Tree), Box<dyn std::error::Error>> {
+    let mut parser = ParserPool::global().get()?;
+    let tree = parser
+        .parse(source_code, previous)
+        .ok_or("The parser gave up on the file")?;
     let root_node = tree.root_node();
+    let package = parsers::extract_package(source_code, root_node)?;
+    let imports = parsers::extract_imports(source_code, root_node)?;
 
-    let mut i = 0;
-    let mut class_node = None;
-    while i < root_node.child_count() {
-        let child = root_node.child(i);
-        if child.is_none() {
-            break;
+    let mut diagnostics = Vec::new();
+    collect_syntax_errors(root_node, file_path, &mut diagnostics);
+
+    let mut classes = Vec::new();
+    for declaration in type_declarations(root_node) {
+        // One broken declaration shouldn't take the rest of the file with it
+        let result = parse_type_declaration(source_code, declaration, &package, None, &mut classes);
+        if let Err(e) = result {
+            diagnostics.push(diagnostic(
+                file_path,
+                declaration,
+                Severity::Error,
+                format!("Failed to parse declaration: {}", e),
+            ));
         }
-        if [
-            "class_declaration",
-            "interface_declaration",
-            "enum_declaration",
-            "record_declaration"
-        ]
-        .contains(&child.unwrap().kind())
-        {
-            class_node = Some(child.unwrap());
-            break;
+    }
+
+    if classes.is_empty() {
+        diagnostics.push(diagnostic(
+            file_path,
+            root_node,
+            Severity::Warning,
+            String::from("No class found"),
+        ));
+    }
+
+    // Names are resolved once every file is parsed, see `type_resolver`
+    for class in &mut classes {
+        class.imports = imports.clone();
+        set_file(class, file_path);
+    }
+
+    let parsed = ParsedFile {
+        classes,
+        diagnostics,
+    };
+    Ok((parsed, tree))
+}
+
+/// Parse files in parallel, a file that can't be read or parsed becomes a diagnostic.
+/// Files whose content didn't change since they went into the cache stored at `cache` aren't
+/// parsed again. The cache is only locked to look them up and to store the new results.
+/// Classes and diagnostics keep the order of `file_paths`.
+pub fn parse_java_files(file_paths: &[String], cache: Option<&Path>) -> ParsedFile {
+    let sources = file_paths
+        .par_iter()
+        .map(|path| {
+            let source_code = fs::read_to_string(path)?;
+            let hash = content_hash(&source_code);
+            Ok((source_code, hash))
+        })
+        .collect::<Vec<std::io::Result<_>>>();
+    let cached = match cache {
+        Some(cache) => with_cache(cache, |cache| {
+            file_paths
+                .iter()
+                .zip(&sources)
+                .map(|(path, source)| {
+                    let (_, hash) = source.as_ref().ok()?;
+                    cache.get(path, *hash).cloned()
+                })
+                .collect()
+        }),
+        None => vec![None; file_paths.len()],
+    };
+
+    // The hash is only returned for fresh results, which still have to go into the cache
+    let parse = |path: &String, source: std::io::Result<(String, u64)>| {
+        let (source_code, hash) = source?;
+        let parsed = parse_java_source(path, &source_code)?;
+        Ok::<_, Box<dyn std::error::Error>>((parsed, Some(hash)))
+    };
+    let results = file_paths
+        .par_iter()
+        .zip(sources)
+        .zip(cached)
+        .map(|((path, source), cached)| match cached {
+            Some(parsed) => (parsed, None),
+            None => parse(path, source).unwrap_or_else(|e| {
+                let diagnostic = Diagnostic {
+                    file: path.to_owned(),
+                    line: 1,
+                    column: 1,
+                    severity: Severity::Error,
+                    message: format!("Failed to parse {}: {}", path, e),
+                };
+                let parsed = ParsedFile {
+                    classes: Vec::new(),
+                    diagnostics: vec![diagnostic],
+                };
+                (parsed, None)
+            }),
+        })
+        .collect::<Vec<_>>();
+
+    let fresh = results.iter().any(|(_, hash)| hash.is_some());
+    if let (Some(cache), true) = (cache, fresh) {
+        with_cache(cache, |cache| {
+            for (path, (result, hash)) in file_paths.iter().zip(&results) {
+                if let Some(hash) = hash {
+                    cache.insert(path.to_owned(), *hash, result.clone());
+                }
+            }
+        });
+    }
+
+    let mut parsed = ParsedFile {
+        classes: Vec::new(),
+        diagnostics: Vec::new(),
+    };
+    for (result, _) in results {
+        parsed.classes.extend(result.classes);
+        parsed.diagnostics.extend(result.diagnostics);
+    }
+    parsed
+}
+
+/// Top level type declarations, including those a syntax error wrapped in an `ERROR` node
+fn type_declarations(root_node: Node<'_>) -> Vec<Node<'_>> {
+    let mut declarations = Vec::new();
+    let mut cursor = root_node.walk();
+    for child in root_node.children(&mut cursor) {
+        if TYPE_DECLARATIONS.contains(&child.kind()) {
+            declarations.push(child);
+        } else if child.is_error() {
+            let mut error_cursor = child.walk();
+            declarations.extend(
+                child
+                    .children(&mut error_cursor)
+                    .filter(|node| TYPE_DECLARATIONS.contains(&node.kind())),
+            );
         }
-        i += 1;
     }
+    declarations
+}
 
-    if class_node.is_none() {
-        return Err("No class found".into());
+/// Report the outermost `ERROR` and every `MISSING` node below `node`
+fn collect_syntax_errors(node: Node<'_>, file_path: &str, diagnostics: &mut Vec<Diagnostic>) {
+    if node.is_error() {
+        let message = String::from("Syntax error");
+        diagnostics.push(diagnostic(file_path, node, Severity::Error, message));
+        return;
+    }
+    if node.is_missing() {
+        let message = format!("Missing `{}`", node.kind());
+        diagnostics.push(diagnostic(file_path, node, Severity::Error, message));
+        return;
+    }
+    if !node.has_error() {
+        return;
+    }
+    let mut cursor = node.walk();
+    for child in node.children(&mut cursor) {
+        collect_syntax_errors(child, file_path, diagnostics);
     }
+}
 
-    // println!("{:?}", class_node.unwrap().kind());
+fn diagnostic(file_path: &str, node: Node<'_>, severity: Severity, message: String) -> Diagnostic {
+    let position = node.start_position();
+    Diagnostic {
+        file: file_path.to_string(),
+        line: position.row + 1,
+        column: position.column + 1,
+        severity,
+        message,
+    }
+}
 
-    let class_info = match class_node.unwrap().kind() {
-        "class_declaration" => parsers::class_parser::parse_java_class(&source_code, root_node),
+/// Parse a type declaration and, recursively, every member type declared in its body.
+/// Nested types are named `Outer.Inner` and keep a reference to their owner.
+fn parse_type_declaration(
+    source_code: &str,
+    node: Node<'_>,
+    package: &str,
+    outer: Option<&str>,
+    classes: &mut Vec<ClassInfo>,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let mut class_info = match node.kind() {
+        "class_declaration" => parsers::class_parser::parse_java_class(source_code, node),
         "interface_declaration" => {
-            parsers::interface_parser::parse_java_interface(&source_code, root_node)
+            parsers::interface_parser::parse_java_interface(source_code, node)
+        }
+        "enum_declaration" => parsers::enum_parser::parse_java_enum(source_code, node),
+        "record_declaration" => parsers::record_parser::parse_java_record(source_code, node),
+        "annotation_type_declaration" => {
+            parsers::annotation_parser::parse_java_annotation(source_code, node)
         }
-        "enum_declaration" => parsers::enum_parser::parse_java_enum(&source_code, root_node),
-        "record_declaration" => parsers::record_parser::parse_java_record(&source_code, root_node),
         _ => Err("Unsupported class type".into()),
     }?;
-    Ok(class_info)
+
+    // Header queries can miss declarations they don't fully match, the name field never does
+    if class_info.name.is_empty() {
+        if let Some(name) = node.child_by_field_name("name") {
+            class_info.name = name.utf8_text(source_code.as_bytes())?.to_string();
+        }
+    }
+    class_info.package = package.to_string();
+
+    // Modifiers and permits clauses look the same on every kind of declaration
+    let (_, modifiers) = parse_modifiers(node);
+    class_info.modifiers = modifiers;
+    class_info.annotations = parse_annotations(source_code, node)?;
+    class_info.location = parsers::node_location(node);
+    if let Some(permits) = node.child_by_field_name("permits") {
+        class_info.permits = parse_type_list(source_code, permits)?;
+    }
+
+    if let Some(outer) = outer {
+        class_info.name = format!("{}.{}", outer, class_info.name);
+        class_info.outer = Some(outer.to_string());
+    }
+    let name = class_info.name.clone();
+    classes.push(class_info);
+
+    let body = match node.child_by_field_name("body") {
+        Some(body) => body,
+        None => return Ok(()),
+    };
+    for member in body_declarations(body) {
+        if TYPE_DECLARATIONS.contains(&member.kind()) {
+            parse_type_declaration(source_code, member, package, Some(&name), classes)?;
+        }
+    }
+
+    Ok(())
+}
+
+/// Record the file every location of a class points into
+fn set_file(class: &mut ClassInfo, file_path: &str) {
+    let parameters = |parameters: &mut Vec<Parameter>| {
+        for parameter in parameters {
+            parameter.location.file = file_path.to_string();
+        }
+    };
+    class.location.file = file_path.to_string();
+    for field in &mut class.fields {
+        field.location.file = file_path.to_string();
+    }
+    for constructor in &mut class.constructors {
+        constructor.location.file = file_path.to_string();
+        parameters(&mut constructor.parameters);
+    }
+    for constant in &mut class.enum_constants {
+        constant.location.file = file_path.to_string();
+    }
+    let constant_methods = class.enum_constants.iter_mut().flat_map(|c| &mut c.methods);
+    for method in class.methods.iter_mut().chain(constant_methods) {
+        method.location.file = file_path.to_string();
+        parameters(&mut method.parameters);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(source_code: &str) -> Vec<ClassInfo> {
+        parse_java_source("Shop.java", source_code).unwrap().classes
+    }
+
+    #[test]
+    fn every_top_level_and_nested_type() {
+        let classes = parse(
+            "package shop;
+            class Order { static class Line { enum State { OPEN } } record Total(int cents) {} }
+            interface Priced {}",
+        );
+        let names = classes.iter().map(|c| c.name.as_str()).collect::<Vec<_>>();
+        assert_eq!(
+            names,
+            [
+                "Order",
+                "Order.Line",
+                "Order.Line.State",
+                "Order.Total",
+                "Priced"
+            ]
+        );
+        assert_eq!(classes[2].qualified_name(), "shop.Order.Line.State");
+        assert_eq!(classes[2].outer.as_deref(), Some("Order.Line"));
+        assert_eq!(classes[4].outer, None);
+    }
+
+    #[test]
+    fn syntax_errors_become_diagnostics() {
+        let parsed = parse_java_source(
+            "Shop.java",
+            "package shop;
+class Order { int id }
+class Line { void add( { } }
+class Customer { String name; }",
+        )
+        .unwrap();
+        // The classes around the broken parts are still there
+        let names = parsed.classes.iter().map(|c| c.name.as_str());
+        assert!(names.clone().any(|name| name == "Order"));
+        assert!(names.clone().any(|name| name == "Customer"));
+        assert!(!parsed.diagnostics.is_empty());
+        assert!(parsed
+            .diagnostics
+            .iter()
+            .all(|d| d.file == "Shop.java" && d.severity == Severity::Error));
+        let missing = parsed
+            .diagnostics
+            .iter()
+            .find(|d| d.message == "Missing `;`");
+        assert_eq!(missing.map(|d| (d.line, d.column)), Some((2, 21)));
+    }
+
+    #[test]
+    fn files_without_classes_are_reported() {
+        let parsed = parse_java_source("Empty.java", "package shop;").unwrap();
+        assert!(parsed.classes.is_empty());
+        assert_eq!(parsed.diagnostics[0].severity, Severity::Warning);
+        assert_eq!(parsed.diagnostics[0].message, "No class found");
+    }
+
+    #[test]
+    fn parallel_parse_keeps_the_file_order() {
+        let dir = std::env::temp_dir().join(format!("jdg-parse-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let mut files = (0..20)
+            .map(|i| {
+                let path = dir.join(format!("C{}.java", i));
+                fs::write(&path, format!("class C{} {{}}", i)).unwrap();
+                path.to_string_lossy().into_owned()
+            })
+            .collect::<Vec<_>>();
+        let missing = dir.join("Missing.java").to_string_lossy().into_owned();
+        files.insert(3, missing.clone());
+
+        let parsed = parse_java_files(&files, None);
+        fs::remove_dir_all(&dir).unwrap();
+        let names = parsed.classes.iter().map(|c| c.name.clone());
+        let expected = (0..20).map(|i| format!("C{}", i));
+        assert_eq!(names.collect::<Vec<_>>(), expected.collect::<Vec<_>>());
+        assert_eq!(parsed.diagnostics.len(), 1);
+        assert_eq!(parsed.diagnostics[0].file, missing);
+    }
+
+    #[test]
+    fn fresh_results_go_into_the_cache() {
+        let dir = std::env::temp_dir().join(format!("jdg-cache-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let files = [dir.join("Order.java").to_string_lossy().into_owned()];
+        let cache = dir.join("cache.json");
+        let cached = |source_code: &str| {
+            let hash = content_hash(source_code);
+            with_cache(&cache, |cache| cache.get(&files[0], hash).is_some())
+        };
+
+        fs::write(&files[0], "class Order {}").unwrap();
+        parse_java_files(&files, Some(&cache));
+        assert!(cached("class Order {}"));
+        fs::write(&files[0], "class Order { int id; }").unwrap();
+        let parsed = parse_java_files(&files, Some(&cache));
+        assert_eq!(parsed.classes[0].fields[0].name, "id");
+        assert!(cached("class Order { int id; }"));
+        fs::remove_dir_all(&dir).unwrap();
+    }
 }
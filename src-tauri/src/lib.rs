@@ -1,7 +1,13 @@
+mod commands;
 mod diagram_generator;
-mod java_parser;
+pub mod java_parser;
+mod neighborhood;
+mod parse_cache;
+mod parser_pool;
 mod parsers;
-mod commands;
+mod project_watcher;
+mod symbol_index;
+mod type_resolver;
 mod types;
 
 #[cfg_attr(mobile, tauri::mobile_entry_point)]
@@ -22,6 +28,11 @@ pub fn run() {
             commands::get_projects,
             commands::read_file_structure,
             commands::generate_mermaid_class_diagram,
+            commands::generate_neighborhood_diagram,
+            commands::open_in_editor,
+            commands::get_settings,
+            commands::save_settings,
+            commands::search_symbols,
         ])
         .run(tauri::generate_context!())
         .expect("error while running tauri application");
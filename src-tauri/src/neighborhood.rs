@@ -0,0 +1,180 @@
+use std::collections::{HashMap, HashSet, VecDeque};
+
+use serde::{Deserialize, Serialize};
+
+use crate::diagram_generator::{association_target, dependency_types, DiagramOptions};
+use crate::types::{ClassInfo, TypeRef};
+
+/// Relationships followed when gathering the neighborhood of a class
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(default)]
+pub struct NeighborhoodOptions {
+    pub supertypes: bool,   // extended classes and implemented interfaces
+    pub subtypes: bool,     // classes extending or implementing it
+    pub associations: bool, // types of its fields
+    pub dependencies: bool, // types its methods and constructors use
+}
+
+impl Default for NeighborhoodOptions {
+    fn default() -> Self {
+        NeighborhoodOptions {
+            supertypes: true,
+            subtypes: true,
+            associations: true,
+            dependencies: true,
+        }
+    }
+}
+
+/// The class named `seed` and every class reachable from it in at most `depth` hops over the
+/// enabled relationships, in the order of `classes`. None when no class has that qualified name.
+/// Type references have to be resolved, only project classes are followed.
+pub fn neighborhood(
+    classes: &[ClassInfo],
+    seed: &str,
+    depth: usize,
+    options: &NeighborhoodOptions,
+    diagram_options: &DiagramOptions,
+) -> Option<Vec<ClassInfo>> {
+    let indexes = classes
+        .iter()
+        .enumerate()
+        .map(|(i, class)| (class.qualified_name(), i))
+        .collect::<HashMap<_, _>>();
+    let index_of = |type_ref: &TypeRef| {
+        let qualified_name = type_ref.qualified_name.as_ref()?;
+        indexes.get(qualified_name).copied()
+    };
+
+    let mut subtypes = vec![Vec::new(); classes.len()];
+    if options.subtypes {
+        for (i, class) in classes.iter().enumerate() {
+            for supertype in class.extends.iter().chain(&class.implements) {
+                if let Some(supertype) = index_of(supertype) {
+                    subtypes[supertype].push(i);
+                }
+            }
+        }
+    }
+
+    let neighbors = |i: usize| {
+        let class = &classes[i];
+        let mut types = Vec::new();
+        if options.associations {
+            // Containers and wrappers lead to their elements, like in the diagram
+            let fields = class.fields.iter();
+            types
+                .extend(fields.map(|field| association_target(&field.type_ref, diagram_options).0));
+        }
+        if options.dependencies {
+            types.extend(
+                dependency_types(class)
+                    .into_iter()
+                    .map(|(type_ref, _)| type_ref),
+            );
+        }
+        if options.supertypes {
+            types.extend(class.extends.iter().chain(&class.implements));
+        }
+        let mut neighbors = types.into_iter().filter_map(index_of).collect::<Vec<_>>();
+        neighbors.extend(&subtypes[i]);
+        neighbors
+    };
+
+    // Breadth first, so every class is reached over its shortest path
+    let seed = *indexes.get(seed)?;
+    let mut reached = HashSet::from([seed]);
+    let mut queue = VecDeque::from([(seed, 0)]);
+    while let Some((i, distance)) = queue.pop_front() {
+        if distance == depth {
+            continue;
+        }
+        for neighbor in neighbors(i) {
+            if reached.insert(neighbor) {
+                queue.push_back((neighbor, distance + 1));
+            }
+        }
+    }
+
+    let mut reached = reached.into_iter().collect::<Vec<_>>();
+    reached.sort_unstable();
+    Some(reached.into_iter().map(|i| classes[i].clone()).collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::java_parser::parse_java_source;
+    use crate::type_resolver::resolve_types;
+
+    const SHOP: &str = r#"
+        package shop;
+        import java.util.List;
+        class Customer { Address address; }
+        class Address {}
+        class Order extends Entity { Customer customer; List<Line> lines; }
+        class Line { Product product; }
+        class Product {}
+        abstract class Entity {}
+        class Invoice extends Entity {}
+        class Checkout { void pay(Order order) {} }
+    "#;
+
+    fn names(depth: usize, options: &NeighborhoodOptions) -> Vec<String> {
+        let mut classes = parse_java_source("Shop.java", SHOP).unwrap().classes;
+        resolve_types(&mut classes, &HashSet::new());
+        let reached = neighborhood(
+            &classes,
+            "shop.Order",
+            depth,
+            options,
+            &DiagramOptions::default(),
+        );
+        reached
+            .unwrap()
+            .into_iter()
+            .map(|class| class.name)
+            .collect()
+    }
+
+    #[test]
+    fn depth_zero_is_the_class_alone() {
+        assert_eq!(names(0, &NeighborhoodOptions::default()), ["Order"]);
+    }
+
+    #[test]
+    fn depth_one_reaches_direct_neighbors() {
+        // Checkout uses Order, but only subtypes are followed backwards
+        assert_eq!(
+            names(1, &NeighborhoodOptions::default()),
+            ["Customer", "Order", "Line", "Entity"]
+        );
+    }
+
+    #[test]
+    fn depth_two_goes_one_hop_further() {
+        assert_eq!(
+            names(2, &NeighborhoodOptions::default()),
+            ["Customer", "Address", "Order", "Line", "Product", "Entity", "Invoice"]
+        );
+    }
+
+    #[test]
+    fn only_enabled_relationships_are_followed() {
+        let supertypes = NeighborhoodOptions {
+            supertypes: true,
+            subtypes: false,
+            associations: false,
+            dependencies: false,
+        };
+        assert_eq!(names(2, &supertypes), ["Order", "Entity"]);
+    }
+
+    #[test]
+    fn unknown_seed() {
+        let classes = parse_java_source("Shop.java", SHOP).unwrap().classes;
+        let options = NeighborhoodOptions::default();
+        let reached = neighborhood(&classes, "Order", 1, &options, &DiagramOptions::default());
+        assert!(reached.is_none());
+    }
+}
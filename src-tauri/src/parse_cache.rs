@@ -0,0 +1,126 @@
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::{Mutex, OnceLock};
+
+use log::warn;
+use serde::{Deserialize, Serialize};
+
+use crate::types::ParsedFile;
+
+/// Bump when the parser output changes between releases, caches of other versions are dropped
+const PARSER_REVISION: u32 = 5;
+
+static CACHES: OnceLock<Mutex<HashMap<PathBuf, ParseCache>>> = OnceLock::new();
+
+/// Parse results of one project, stored on disk so they survive restarts.
+/// An entry is only used while the file still has the content hash it was parsed with.
+pub struct ParseCache {
+    path: PathBuf,
+    entries: HashMap<String, CacheEntry>,
+    dirty: bool,
+}
+
+#[derive(Serialize, Deserialize)]
+struct CacheFile {
+    version: String,
+    entries: HashMap<String, CacheEntry>,
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+struct CacheEntry {
+    hash: u64,
+    parsed: ParsedFile,
+}
+
+fn parser_version() -> String {
+    format!("{}+{}", env!("CARGO_PKG_VERSION"), PARSER_REVISION)
+}
+
+impl ParseCache {
+    /// Load the cache stored at `path`. A missing, unreadable or outdated cache starts empty,
+    /// entries of files that no longer exist are dropped.
+    pub fn load(path: &Path) -> ParseCache {
+        let mut cache = ParseCache {
+            path: path.to_path_buf(),
+            entries: HashMap::new(),
+            dirty: false,
+        };
+        let json = match std::fs::read_to_string(path) {
+            Ok(json) => json,
+            Err(_) => return cache,
+        };
+        match serde_json::from_str::<CacheFile>(&json) {
+            Ok(file) if file.version == parser_version() => {
+                let count = file.entries.len();
+                cache.entries = file
+                    .entries
+                    .into_iter()
+                    .filter(|(file_path, _)| Path::new(file_path).exists())
+                    .collect();
+                cache.dirty = cache.entries.len() != count;
+            }
+            Ok(_) => cache.dirty = true,
+            Err(e) => warn!("Ignoring parse cache {}: {}", path.display(), e),
+        }
+        cache
+    }
+
+    /// Parse result of `file_path`, if it was parsed with the same content
+    pub fn get(&self, file_path: &str, hash: u64) -> Option<&ParsedFile> {
+        self.entries
+            .get(file_path)
+            .filter(|entry| entry.hash == hash)
+            .map(|entry| &entry.parsed)
+    }
+
+    pub fn insert(&mut self, file_path: String, hash: u64, parsed: ParsedFile) {
+        self.entries.insert(file_path, CacheEntry { hash, parsed });
+        self.dirty = true;
+    }
+
+    pub fn remove(&mut self, file_path: &str) {
+        if self.entries.remove(file_path).is_some() {
+            self.dirty = true;
+        }
+    }
+
+    /// Write the cache back to disk if anything changed since it was loaded
+    pub fn save(&mut self) -> Result<(), Box<dyn std::error::Error>> {
+        if !self.dirty {
+            return Ok(());
+        }
+        if let Some(dir) = self.path.parent() {
+            std::fs::create_dir_all(dir)?;
+        }
+        let file = CacheFile {
+            version: parser_version(),
+            entries: self.entries.clone(),
+        };
+        std::fs::write(&self.path, serde_json::to_string(&file)?)?;
+        self.dirty = false;
+        Ok(())
+    }
+}
+
+/// Run `f` with the cache stored at `path`, loading it on first use and saving it afterwards
+pub fn with_cache<T>(path: &Path, f: impl FnOnce(&mut ParseCache) -> T) -> T {
+    let mut caches = CACHES
+        .get_or_init(|| Mutex::new(HashMap::new()))
+        .lock()
+        .unwrap_or_else(|poisoned| poisoned.into_inner());
+    let cache = caches
+        .entry(path.to_path_buf())
+        .or_insert_with(|| ParseCache::load(path));
+    let result = f(cache);
+    if let Err(e) = cache.save() {
+        warn!("Failed to save parse cache {}: {}", path.display(), e);
+    }
+    result
+}
+
+/// FNV-1a hash of a file's content, stable across runs and Rust versions
+pub fn content_hash(source_code: &str) -> u64 {
+    source_code.bytes().fold(0xcbf29ce484222325, |hash, byte| {
+        (hash ^ byte as u64).wrapping_mul(0x100000001b3)
+    })
+}
@@ -0,0 +1,79 @@
+use std::ops::{Deref, DerefMut};
+use std::sync::{Mutex, OnceLock};
+
+use tree_sitter::Parser;
+
+static POOL: OnceLock<ParserPool> = OnceLock::new();
+
+/// Java parsers kept around between files. A parser can only work on one file at a time,
+/// so every thread takes its own and hands it back when done.
+pub struct ParserPool {
+    parsers: Mutex<Vec<Parser>>,
+}
+
+impl ParserPool {
+    /// The pool shared by the whole application
+    pub fn global() -> &'static ParserPool {
+        POOL.get_or_init(|| ParserPool {
+            parsers: Mutex::new(Vec::new()),
+        })
+    }
+
+    /// Take an idle parser, or set up a new one when all of them are busy
+    pub fn get(&self) -> Result<PooledParser<'_>, Box<dyn std::error::Error>> {
+        let idle = self
+            .parsers
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+            .pop();
+        let parser = match idle {
+            Some(parser) => parser,
+            None => {
+                let mut parser = Parser::new();
+                parser.set_language(tree_sitter_java::language())?;
+                parser
+            }
+        };
+        Ok(PooledParser {
+            pool: self,
+            parser: Some(parser),
+        })
+    }
+}
+
+/// A parser borrowed from the pool, it goes back to the pool when dropped
+pub struct PooledParser<'a> {
+    pool: &'a ParserPool,
+    parser: Option<Parser>, // only taken when dropped
+}
+
+impl Deref for PooledParser<'_> {
+    type Target = Parser;
+
+    fn deref(&self) -> &Parser {
+        self.parser
+            .as_ref()
+            .expect("parser is only taken when dropped")
+    }
+}
+
+impl DerefMut for PooledParser<'_> {
+    fn deref_mut(&mut self) -> &mut Parser {
+        self.parser
+            .as_mut()
+            .expect("parser is only taken when dropped")
+    }
+}
+
+impl Drop for PooledParser<'_> {
+    fn drop(&mut self) {
+        if let Some(mut parser) = self.parser.take() {
+            parser.reset();
+            self.pool
+                .parsers
+                .lock()
+                .unwrap_or_else(|poisoned| poisoned.into_inner())
+                .push(parser);
+        }
+    }
+}
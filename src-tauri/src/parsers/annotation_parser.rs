@@ -0,0 +1,43 @@
+use tree_sitter::Node;
+
+use crate::types::{ClassInfo, ClassType};
+
+use super::member_parser::parse_members;
+
+/// Parse an `@interface` annotation type declaration.
+/// Its elements (`String value() default "";`) are kept as methods, its constants as fields.
+pub fn parse_java_annotation(
+    source_code: &str,
+    node: Node<'_>,
+) -> Result<ClassInfo, Box<dyn std::error::Error>> {
+    let mut class_info = ClassInfo::new(ClassType::Annotation);
+
+    if let Some(name) = node.child_by_field_name("name") {
+        class_info.name = name.utf8_text(source_code.as_bytes())?.to_string();
+    }
+
+    // Parse members declared directly in the body
+    if let Some(body) = node.child_by_field_name("body") {
+        parse_members(source_code, body, &mut class_info)?;
+    }
+
+    Ok(class_info)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::java_parser::parse_java_source;
+    use crate::types::ClassType;
+
+    #[test]
+    fn annotation_type_elements_and_constants() {
+        let source_code = "@interface Audited { int LEVEL = 1; String value() default \"\"; }";
+        let parsed = parse_java_source("Audited.java", source_code).unwrap();
+        assert!(parsed.diagnostics.is_empty());
+        let audited = &parsed.classes[0];
+        assert!(matches!(audited.class_type, ClassType::Annotation));
+        assert_eq!(audited.name, "Audited");
+        assert_eq!(audited.fields[0].name, "LEVEL");
+        assert_eq!(audited.methods[0].name, "value");
+    }
+}
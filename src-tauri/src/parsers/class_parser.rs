@@ -1,178 +1,118 @@
-use tree_sitter::{Node, Query, QueryCursor};
+use std::sync::OnceLock;
 
-use crate::types::{ClassField, ClassInfo, ClassMethod, ClassType};
+use tree_sitter::{Node, Query};
 
-use super::extract_package;
+use crate::types::{ClassInfo, ClassType};
+
+use super::member_parser::parse_members;
+use super::type_parser::{parse_type, parse_type_parameters};
+use super::{for_each_own_capture, shared_query};
+
+static CLASS_QUERY: OnceLock<Query> = OnceLock::new();
 
 pub fn parse_java_class(
     source_code: &str,
-    root_node: Node<'_>,
+    node: Node<'_>,
 ) -> Result<ClassInfo, Box<dyn std::error::Error>> {
-    let language = tree_sitter_java::language();
-    let class_query = Query::new(
-        language,
+    let class_query = shared_query(
+        &CLASS_QUERY,
         r#"
         (class_declaration
             (modifiers "abstract" @abstract)?
             name: (identifier) @class_name
-            (superclass
-                (type_identifier) @extends)?
-            (super_interfaces
-                (type_list
-                    (type_identifier) @implements)*)?
+            type_parameters: (type_parameters)? @type_parameters
+            superclass: (superclass (_) @extends)?
+            interfaces: (super_interfaces
+                (type_list (_) @implements))?
     )
     "#,
     )?;
 
-    let mut class_cursor = QueryCursor::new();
-
-    let matches = class_cursor.matches(&class_query, root_node, source_code.as_bytes());
-    let class_matches = matches.collect::<Vec<_>>();
-
-    // Extract class information
-    let mut class_info = ClassInfo {
-        name: String::new(),
-        package: extract_package(&source_code, root_node)?,
-        fields: Vec::new(),
-        methods: Vec::new(),
-        extends: None,
-        implements: Vec::new(),
-        class_type: ClassType::Class,
-    };
-
-    let capture_names = class_query.capture_names();
-
-    // print out the match
-    for match_ in class_matches {
-        // println!("Match: {:?}", match_);
-        for capture in match_.captures {
-            let capture_index = capture.index as usize;
-            let capture_name = &capture_names[capture_index];
-            match capture_name.as_str() {
+    let mut class_info = ClassInfo::new(ClassType::Class);
+    for_each_own_capture(
+        class_query,
+        source_code,
+        node,
+        "class_name",
+        |name, capture| {
+            match name {
                 "class_name" => {
-                    class_info.name = capture.node.utf8_text(source_code.as_bytes())?.to_string()
+                    class_info.name = capture.utf8_text(source_code.as_bytes())?.to_string()
                 }
                 "abstract" => class_info.class_type = ClassType::AbstractClass,
-                "extends" => {
-                    class_info.extends =
-                        Some(capture.node.utf8_text(source_code.as_bytes())?.to_string())
+                "type_parameters" => {
+                    class_info.type_parameters = parse_type_parameters(source_code, capture)?
                 }
+                "extends" => class_info.extends = Some(parse_type(source_code, capture)?),
                 "implements" => class_info
                     .implements
-                    .push(capture.node.utf8_text(source_code.as_bytes())?.to_string()),
+                    .push(parse_type(source_code, capture)?),
                 _ => {}
             }
-        }
-        // println!("Class: {:?}", class_info); // Debug print for class info
-    }
-
-    // Parse fields
-    let field_query = Query::new(
-        language,
-        "
-        (field_declaration
-            (modifiers
-                [\"public\" \"private\" \"protected\"] @visibility)?
-            type: (_) @type
-            declarator: (variable_declarator
-                name: (identifier) @name))
-    ",
+            Ok(())
+        },
     )?;
 
-    let mut query_cursor = QueryCursor::new();
-    let field_matches = query_cursor.matches(&field_query, root_node, source_code.as_bytes());
-    for match_ in field_matches {
-        // println!("Match: {:?}", match_); // Debug print for match
-        let mut field = ClassField {
-            name: String::new(),
-            type_name: String::new(),
-            visibility: String::new(),
-        };
-
-        for capture in match_.captures {
-            match capture.node.kind() {
-                "identifier" => {
-                    field.name = capture.node.utf8_text(source_code.as_bytes())?.to_string()
-                }
-                "private" => field.visibility = "private".to_string(),
-                "public" => field.visibility = "public".to_string(),
-                "protected" => field.visibility = "protected".to_string(),
-                "type_identifier" => {
-                    field.type_name = capture.node.utf8_text(source_code.as_bytes())?.to_string()
-                }
-                "generic_type" => {
-                    field.type_name = capture.node.utf8_text(source_code.as_bytes())?.to_string()
-                }
-                "boolean_type" => {
-                    field.type_name = capture.node.utf8_text(source_code.as_bytes())?.to_string()
-                }
-                "visibility" => {
-                    field.visibility = capture.node.utf8_text(source_code.as_bytes())?.to_string()
-                }
-                _ => {}
-            }
-        }
-
-        // println!("Field: {:?}", field); // Debug print for field identifie
-
-        class_info.fields.push(field);
+    // Parse members declared directly in the body
+    if let Some(body) = node.child_by_field_name("body") {
+        parse_members(source_code, body, &mut class_info)?;
     }
 
-    // Parse methods
-    let method_query = Query::new(
-        language,
-        "
-        (method_declaration
-            (modifiers
-                [\"public\" \"private\" \"protected\"] @visibility)?
-            type: (_) @return_type
-            name: (identifier) @name
-            parameters: (formal_parameters
-                (formal_parameter
-                    type: (_) @param_type
-                    name: (identifier) @param_name)*))
-    ",
-    )?;
-
-    let method_matches = query_cursor.matches(&method_query, root_node, source_code.as_bytes());
-    for match_ in method_matches {
-        // println!("Match: {:?}", match_); // Debug print for match
-        let mut method = ClassMethod {
-            name: String::new(),
-            return_type: String::new(),
-            visibility: String::new(),
-            parameters: Vec::new(),
-        };
-
-        for capture in match_.captures {
-            let index = capture.index as usize;
-            match index {
-                0 => {
-                    method.visibility = capture.node.utf8_text(source_code.as_bytes())?.to_string()
-                }
-                1 => {
-                    method.return_type = capture.node.utf8_text(source_code.as_bytes())?.to_string()
-                }
-                2 => method.name = capture.node.utf8_text(source_code.as_bytes())?.to_string(),
-                _ => {}
-            }
-            // collect all other indexes and combine them into a string
-            if index > 2 && index % 2 != 0 {
-                let param_name = capture.node.utf8_text(source_code.as_bytes())?.to_string();
-                let param_type = capture
-                    .node
-                    .next_sibling()
-                    .and_then(|n| n.utf8_text(source_code.as_bytes()).ok())
-                    .unwrap_or("")
-                    .to_string();
-                method.parameters.push((param_name, param_type));
-            }
-        }
-
-        // println!("Method: {:?}", method); // Debug print for method
+    Ok(class_info)
+}
 
-        class_info.methods.push(method);
+#[cfg(test)]
+mod tests {
+    use crate::java_parser::parse_java_source;
+    use crate::types::ClassType;
+
+    #[test]
+    fn members_of_nested_anonymous_and_local_classes_stay_out() {
+        let source_code = "class Order {
+            int id;
+            static class Builder { int size; Builder size(int size) { return this; } }
+            java.util.Comparator<Order> byId = new java.util.Comparator<>() {
+                int calls;
+                public int compare(Order a, Order b) { return 0; }
+            };
+            void print() { class Printer { String prefix; void run() {} } }
+        }";
+        let classes = parse_java_source("Order.java", source_code)
+            .unwrap()
+            .classes;
+        let fields = classes[0].fields.iter().map(|f| f.name.as_str());
+        let methods = classes[0].methods.iter().map(|m| m.name.as_str());
+        assert_eq!(fields.collect::<Vec<_>>(), ["id", "byId"]);
+        assert_eq!(methods.collect::<Vec<_>>(), ["print"]);
+        assert_eq!(classes[1].name, "Order.Builder");
+        assert_eq!(classes[1].fields[0].name, "size");
     }
 
-    Ok(class_info)
+    #[test]
+    fn generic_header_of_the_class_itself() {
+        let source_code = "abstract class OrderRepo<T extends Order, K>
+                extends BaseRepo<T> implements Comparable<OrderRepo<T, K>>, Cloneable {
+            static class Cache extends java.util.HashMap<K, T> implements Runnable {}
+        }";
+        let classes = parse_java_source("OrderRepo.java", source_code)
+            .unwrap()
+            .classes;
+        let repo = &classes[0];
+        assert!(matches!(repo.class_type, ClassType::AbstractClass));
+        let type_parameters = repo.type_parameters.iter().map(|t| t.to_string());
+        assert_eq!(
+            type_parameters.collect::<Vec<_>>(),
+            ["T extends Order", "K"]
+        );
+        assert_eq!(repo.extends.as_ref().unwrap().to_string(), "BaseRepo<T>");
+        let implements = repo.implements.iter().map(|t| t.to_string());
+        assert_eq!(
+            implements.collect::<Vec<_>>(),
+            ["Comparable<OrderRepo<T, K>>", "Cloneable"]
+        );
+        let cache = &classes[1];
+        assert!(matches!(cache.class_type, ClassType::Class));
+        assert_eq!(cache.extends.as_ref().unwrap().name, "HashMap");
+        assert_eq!(cache.implements[0].name, "Runnable");
+    }
 }
@@ -1,60 +1,136 @@
-use tree_sitter::{Node, Query, QueryCursor};
+use std::sync::OnceLock;
 
-use crate::types::{ClassField, ClassInfo, ClassType};
+use tree_sitter::{Node, Query};
 
-use super::extract_package;
+use crate::types::{ClassInfo, ClassType, EnumConstant, Visibility};
+
+use super::member_parser::parse_members;
+use super::type_parser::parse_type;
+use super::{for_each_own_capture, node_location, shared_query};
+
+static ENUM_QUERY: OnceLock<Query> = OnceLock::new();
 
 pub fn parse_java_enum(
     source_code: &str,
-    root_node: Node<'_>,
+    node: Node<'_>,
 ) -> Result<ClassInfo, Box<dyn std::error::Error>> {
-    let language = tree_sitter_java::language();
-    let query = Query::new(
-        language,
+    let query = shared_query(
+        &ENUM_QUERY,
         "(enum_declaration
             name: (identifier) @enum-name
-             (enum_body (enum_constant name: (identifier) @enum-value)))",
+            interfaces: (super_interfaces
+                (type_list (_) @implements))?)",
     )?;
 
-    let mut cursor = QueryCursor::new();
-    let matches = cursor.matches(&query, root_node, source_code.as_bytes());
-    let class_matches = matches.collect::<Vec<_>>();
+    let mut class_info = ClassInfo::new(ClassType::Enum);
+    for_each_own_capture(query, source_code, node, "enum-name", |name, capture| {
+        match name {
+            "enum-name" => class_info.name = capture.utf8_text(source_code.as_bytes())?.to_string(),
+            "implements" => class_info
+                .implements
+                .push(parse_type(source_code, capture)?),
+            _ => {}
+        }
+        Ok(())
+    })?;
+
+    let body = match node.child_by_field_name("body") {
+        Some(body) => body,
+        None => return Ok(class_info),
+    };
+
+    // Parse constants
+    let mut cursor = body.walk();
+    for constant in body.named_children(&mut cursor) {
+        if constant.kind() == "enum_constant" {
+            class_info
+                .enum_constants
+                .push(parse_enum_constant(source_code, constant)?);
+        }
+    }
+
+    // Parse fields, constructors and methods after the constants
+    parse_members(source_code, body, &mut class_info)?;
+
+    // Enum constructors can only be private
+    for constructor in &mut class_info.constructors {
+        constructor.visibility = Visibility::Private;
+    }
+
+    Ok(class_info)
+}
 
-    // Extract class information
-    let mut class_info = ClassInfo {
+/// Parse an enum constant with its constructor arguments and constant-specific body
+fn parse_enum_constant(
+    source_code: &str,
+    node: Node<'_>,
+) -> Result<EnumConstant, Box<dyn std::error::Error>> {
+    let mut constant = EnumConstant {
         name: String::new(),
-        package: extract_package(&source_code, root_node)?,
-        fields: Vec::new(),
+        arguments: Vec::new(),
         methods: Vec::new(),
-        extends: None,
-        implements: Vec::new(),
-        class_type: ClassType::Enum,
+        location: node_location(node),
     };
 
-    let capture_names = query.capture_names();
-
-    // print out the match
-    for match_ in class_matches {
-        // println!("Match: {:?}", match_);
-        for capture in match_.captures {
-            let capture_index = capture.index as usize;
-            let capture_name = &capture_names[capture_index];
-            // println!("Capture: {}", capture_name);
-            match capture_name.as_str() {
-                "enum-name" => {
-                    class_info.name = capture.node.utf8_text(source_code.as_bytes())?.to_string()
-                }
-                "enum-value" => class_info.fields.push(ClassField {
-                    name: capture.node.utf8_text(source_code.as_bytes())?.to_string(),
-                    type_name: String::new(),
-                    visibility: String::new(),
-                }),
-                _ => {}
-            }
+    if let Some(name) = node.child_by_field_name("name") {
+        constant.name = name.utf8_text(source_code.as_bytes())?.to_string();
+    }
+    if let Some(arguments) = node.child_by_field_name("arguments") {
+        let mut cursor = arguments.walk();
+        for argument in arguments.named_children(&mut cursor) {
+            constant
+                .arguments
+                .push(argument.utf8_text(source_code.as_bytes())?.to_string());
         }
-        // println!("Class: {:?}", class_info); // Debug print for class info
+    }
+    if let Some(body) = node.child_by_field_name("body") {
+        // The body is an anonymous subclass, only its methods are of interest
+        let mut constant_class = ClassInfo::new(ClassType::Class);
+        parse_members(source_code, body, &mut constant_class)?;
+        constant.methods = constant_class.methods;
     }
 
-    // Parse fields
-    Ok(class_info)
+    Ok(constant)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::java_parser::parse_java_source;
+    use crate::types::Visibility;
+
+    #[test]
+    fn constants_with_arguments_bodies_and_members() {
+        let source_code = "enum Currency implements Priced, Comparable<Currency> {
+            EUR(\"€\", 2) { String format(long cents) { return \"\"; } },
+            JPY(\"¥\", 0),
+            NONE;
+            private final String symbol;
+            Currency(String symbol, int digits) { this.symbol = symbol; }
+            Currency() { this(\"\", 0); }
+            String format(long cents) { return symbol; }
+        }";
+        let class = &parse_java_source("Currency.java", source_code)
+            .unwrap()
+            .classes[0];
+        let implements = class.implements.iter().map(|t| t.to_string());
+        assert_eq!(
+            implements.collect::<Vec<_>>(),
+            ["Priced", "Comparable<Currency>"]
+        );
+
+        let constants = class.enum_constants.iter().map(|c| c.name.as_str());
+        assert_eq!(constants.collect::<Vec<_>>(), ["EUR", "JPY", "NONE"]);
+        assert_eq!(class.enum_constants[0].arguments, ["\"€\"", "2"]);
+        assert_eq!(class.enum_constants[0].methods[0].name, "format");
+        assert!(class.enum_constants[1].methods.is_empty());
+        assert!(class.enum_constants[2].arguments.is_empty());
+
+        assert_eq!(class.fields[0].name, "symbol");
+        assert_eq!(class.constructors.len(), 2);
+        assert!(class
+            .constructors
+            .iter()
+            .all(|c| c.visibility == Visibility::Private));
+        assert_eq!(class.methods.len(), 1);
+    }
 }
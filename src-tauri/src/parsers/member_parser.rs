@@ -0,0 +1,713 @@
+use tree_sitter::Node;
+
+use crate::types::{
+    Annotation, AnnotationElement, ClassConstructor, ClassField, ClassInfo, ClassMethod, ClassType,
+    FieldValue, Modifier, Parameter, TypeRef, Visibility,
+};
+
+use super::node_location;
+use super::type_parser::{
+    count_dimensions, parse_parameter_type, parse_type, parse_type_parameters,
+};
+
+/// Direct children of a type body; enum bodies keep their members in `enum_body_declarations`
+/// and syntax errors can wrap members that are still intact in an `ERROR` node
+pub fn body_declarations(body: Node<'_>) -> Vec<Node<'_>> {
+    let mut cursor = body.walk();
+    let mut members = Vec::new();
+    for child in body.named_children(&mut cursor) {
+        if child.kind() == "enum_body_declarations" || child.is_error() {
+            let mut inner_cursor = child.walk();
+            members.extend(child.named_children(&mut inner_cursor));
+        } else {
+            members.push(child);
+        }
+    }
+    members
+}
+
+/// Parse the fields, constructors and methods declared directly in a type body.
+/// Members of nested, anonymous and local classes are deeper in the tree and are skipped.
+pub fn parse_members(
+    source_code: &str,
+    body: Node<'_>,
+    class_info: &mut ClassInfo,
+) -> Result<(), Box<dyn std::error::Error>> {
+    // Interface and annotation members are implicitly public, their fields are constants
+    let implicitly_public = matches!(
+        class_info.class_type,
+        ClassType::Interface | ClassType::Annotation
+    );
+
+    for member in body_declarations(body) {
+        match member.kind() {
+            "field_declaration" | "constant_declaration" => {
+                for mut field in parse_fields(source_code, member)? {
+                    if implicitly_public {
+                        make_public(&mut field.visibility);
+                        add_modifier(&mut field.modifiers, Modifier::Static);
+                        add_modifier(&mut field.modifiers, Modifier::Final);
+                    }
+                    class_info.fields.push(field);
+                }
+            }
+            "constructor_declaration" | "compact_constructor_declaration" => class_info
+                .constructors
+                .push(parse_constructor(source_code, member)?),
+            "method_declaration" | "annotation_type_element_declaration" => {
+                let mut method = parse_method(source_code, member)?;
+                if implicitly_public {
+                    make_public(&mut method.visibility);
+                }
+                // Interface methods without a body are abstract unless they're static or default
+                let has_body = member.child_by_field_name("body").is_some();
+                if matches!(class_info.class_type, ClassType::Interface)
+                    && !has_body
+                    && !method.modifiers.contains(&Modifier::Static)
+                {
+                    add_modifier(&mut method.modifiers, Modifier::Abstract);
+                }
+                class_info.methods.push(method)
+            }
+            _ => {}
+        }
+    }
+    parse_field_values(source_code, body, &mut class_info.fields)?;
+    Ok(())
+}
+
+fn make_public(visibility: &mut Visibility) {
+    if *visibility == Visibility::PackagePrivate {
+        *visibility = Visibility::Public;
+    }
+}
+
+fn add_modifier(modifiers: &mut Vec<Modifier>, modifier: Modifier) {
+    if !modifiers.contains(&modifier) {
+        modifiers.push(modifier);
+    }
+}
+
+/// Extract the visibility and the other modifier keywords of a declaration.
+/// Declarations without an access modifier are package-private.
+pub fn parse_modifiers(node: Node<'_>) -> (Visibility, Vec<Modifier>) {
+    let mut visibility = Visibility::PackagePrivate;
+    let mut modifiers = Vec::new();
+
+    let mut cursor = node.walk();
+    let modifiers_node = node
+        .children(&mut cursor)
+        .find(|child| child.kind() == "modifiers");
+    let modifiers_node = match modifiers_node {
+        Some(modifiers_node) => modifiers_node,
+        None => return (visibility, modifiers),
+    };
+
+    let mut cursor = modifiers_node.walk();
+    for child in modifiers_node.children(&mut cursor) {
+        match child.kind() {
+            "public" => visibility = Visibility::Public,
+            "protected" => visibility = Visibility::Protected,
+            "private" => visibility = Visibility::Private,
+            "static" => modifiers.push(Modifier::Static),
+            "final" => modifiers.push(Modifier::Final),
+            "abstract" => modifiers.push(Modifier::Abstract),
+            "default" => modifiers.push(Modifier::Default),
+            "synchronized" => modifiers.push(Modifier::Synchronized),
+            "native" => modifiers.push(Modifier::Native),
+            "transient" => modifiers.push(Modifier::Transient),
+            "volatile" => modifiers.push(Modifier::Volatile),
+            "sealed" => modifiers.push(Modifier::Sealed),
+            "non-sealed" => modifiers.push(Modifier::NonSealed),
+            _ => {}
+        }
+    }
+    (visibility, modifiers)
+}
+
+/// Parse the annotations among a declaration's modifiers, `@Table(name = "orders")` keeps
+/// its element values as written
+pub fn parse_annotations(
+    source_code: &str,
+    node: Node<'_>,
+) -> Result<Vec<Annotation>, Box<dyn std::error::Error>> {
+    let mut annotations = Vec::new();
+    let mut cursor = node.walk();
+    let modifiers = node
+        .children(&mut cursor)
+        .find(|child| child.kind() == "modifiers");
+    let modifiers = match modifiers {
+        Some(modifiers) => modifiers,
+        None => return Ok(annotations),
+    };
+
+    let mut cursor = modifiers.walk();
+    for child in modifiers.named_children(&mut cursor) {
+        if child.kind() != "marker_annotation" && child.kind() != "annotation" {
+            continue;
+        }
+        let mut annotation = Annotation {
+            name: String::new(),
+            qualified_name: None,
+            elements: Vec::new(),
+        };
+        if let Some(name) = child.child_by_field_name("name") {
+            annotation.name = name.utf8_text(source_code.as_bytes())?.to_string();
+        }
+        if let Some(arguments) = child.child_by_field_name("arguments") {
+            let mut arguments_cursor = arguments.walk();
+            for argument in arguments.named_children(&mut arguments_cursor) {
+                let element = match argument.kind() {
+                    "element_value_pair" => {
+                        let key = argument.child_by_field_name("key");
+                        let value = argument.child_by_field_name("value");
+                        match (key, value) {
+                            (Some(key), Some(value)) => AnnotationElement {
+                                name: key.utf8_text(source_code.as_bytes())?.to_string(),
+                                value: value.utf8_text(source_code.as_bytes())?.to_string(),
+                            },
+                            _ => continue,
+                        }
+                    }
+                    _ => AnnotationElement {
+                        name: String::from("value"),
+                        value: argument.utf8_text(source_code.as_bytes())?.to_string(),
+                    },
+                };
+                annotation.elements.push(element);
+            }
+        }
+        annotations.push(annotation);
+    }
+    Ok(annotations)
+}
+
+/// Parse every declarator of a field declaration, `private int x, y;` declares two fields
+fn parse_fields(
+    source_code: &str,
+    node: Node<'_>,
+) -> Result<Vec<ClassField>, Box<dyn std::error::Error>> {
+    let mut fields = Vec::new();
+    let type_node = match node.child_by_field_name("type") {
+        Some(type_node) => type_node,
+        None => return Ok(fields),
+    };
+    let type_ref = parse_type(source_code, type_node)?;
+    let (visibility, modifiers) = parse_modifiers(node);
+    let annotations = parse_annotations(source_code, node)?;
+
+    let mut cursor = node.walk();
+    for declarator in node.children_by_field_name("declarator", &mut cursor) {
+        let name = match declarator.child_by_field_name("name") {
+            Some(name) => name,
+            None => continue,
+        };
+
+        // `int values[]` puts the dimensions on the declarator instead of the type
+        let mut type_ref = type_ref.clone();
+        if let Some(dimensions) = declarator.child_by_field_name("dimensions") {
+            type_ref.array_dimensions += count_dimensions(dimensions);
+        }
+
+        let created = declarator
+            .child_by_field_name("value")
+            .is_some_and(|value| value.kind() == "object_creation_expression");
+        fields.push(ClassField {
+            name: name.utf8_text(source_code.as_bytes())?.to_string(),
+            type_ref,
+            visibility,
+            modifiers: modifiers.clone(),
+            annotations: annotations.clone(),
+            values: created.then_some(FieldValue::Created).into_iter().collect(),
+            location: node_location(node),
+        });
+    }
+    Ok(fields)
+}
+
+/// Parse a constructor; compact record constructors have no parameter list of their own
+fn parse_constructor(
+    source_code: &str,
+    node: Node<'_>,
+) -> Result<ClassConstructor, Box<dyn std::error::Error>> {
+    let (visibility, _) = parse_modifiers(node);
+    let parameters = match node.child_by_field_name("parameters") {
+        Some(parameters) => parse_parameters(source_code, parameters)?,
+        None => Vec::new(),
+    };
+    let used_types = match node.child_by_field_name("body") {
+        Some(body) => parse_used_types(source_code, body)?,
+        None => Vec::new(),
+    };
+    Ok(ClassConstructor {
+        visibility,
+        parameters,
+        throws: parse_throws(source_code, node)?,
+        used_types,
+        annotations: parse_annotations(source_code, node)?,
+        location: node_location(node),
+    })
+}
+
+fn parse_method(
+    source_code: &str,
+    node: Node<'_>,
+) -> Result<ClassMethod, Box<dyn std::error::Error>> {
+    let (visibility, modifiers) = parse_modifiers(node);
+    let mut method = ClassMethod {
+        name: String::new(),
+        return_type: TypeRef::default(),
+        visibility,
+        modifiers,
+        type_parameters: Vec::new(),
+        parameters: Vec::new(),
+        throws: parse_throws(source_code, node)?,
+        used_types: Vec::new(),
+        annotations: parse_annotations(source_code, node)?,
+        location: node_location(node),
+    };
+
+    if let Some(name) = node.child_by_field_name("name") {
+        method.name = name.utf8_text(source_code.as_bytes())?.to_string();
+    }
+    if let Some(type_parameters) = node.child_by_field_name("type_parameters") {
+        method.type_parameters = parse_type_parameters(source_code, type_parameters)?;
+    }
+    if let Some(return_type) = node.child_by_field_name("type") {
+        method.return_type = parse_type(source_code, return_type)?;
+    }
+    if let Some(parameters) = node.child_by_field_name("parameters") {
+        method.parameters = parse_parameters(source_code, parameters)?;
+    }
+    if let Some(body) = node.child_by_field_name("body") {
+        method.used_types = parse_used_types(source_code, body)?;
+    }
+
+    Ok(method)
+}
+
+/// Exception types of a `throws` clause
+fn parse_throws(
+    source_code: &str,
+    node: Node<'_>,
+) -> Result<Vec<TypeRef>, Box<dyn std::error::Error>> {
+    let mut throws = Vec::new();
+    let mut cursor = node.walk();
+    let clause = node
+        .named_children(&mut cursor)
+        .find(|child| child.kind() == "throws");
+    if let Some(clause) = clause {
+        let mut cursor = clause.walk();
+        for exception in clause.named_children(&mut cursor) {
+            throws.push(parse_type(source_code, exception)?);
+        }
+    }
+    Ok(throws)
+}
+
+/// Types a method or constructor body uses: local variable types, `new` expressions and
+/// the classes of static calls such as `Objects.equals(a, b)`
+fn parse_used_types(
+    source_code: &str,
+    body: Node<'_>,
+) -> Result<Vec<TypeRef>, Box<dyn std::error::Error>> {
+    let mut used_types = Vec::new();
+    for node in own_nodes(body) {
+        let type_node = match node.kind() {
+            "local_variable_declaration"
+            | "enhanced_for_statement"
+            | "object_creation_expression"
+            | "array_creation_expression" => node.child_by_field_name("type"),
+            _ => None,
+        };
+        let type_ref = match type_node {
+            Some(type_node) => Some(parse_type(source_code, type_node)?),
+            None => static_call_target(source_code, node)?,
+        };
+        if let Some(type_ref) = type_ref {
+            // `var` leaves the type to the compiler
+            let inferred = type_ref.name == "var" && type_ref.qualifier.is_none();
+            if !type_ref.primitive && !inferred && !used_types.contains(&type_ref) {
+                used_types.push(type_ref);
+            }
+        }
+    }
+    Ok(used_types)
+}
+
+/// The class a method is called on when the call is static. Only names that look like
+/// types count, `orders.add(o)` calls a variable and `LOG.info(m)` a constant.
+fn static_call_target(
+    source_code: &str,
+    node: Node<'_>,
+) -> Result<Option<TypeRef>, Box<dyn std::error::Error>> {
+    if node.kind() != "method_invocation" {
+        return Ok(None);
+    }
+    let object = match node.child_by_field_name("object") {
+        Some(object) if object.kind() == "identifier" => object,
+        _ => return Ok(None),
+    };
+    let name = object.utf8_text(source_code.as_bytes())?;
+    let looks_like_type =
+        name.starts_with(|c: char| c.is_uppercase()) && name.chars().any(|c| c.is_lowercase());
+    Ok(looks_like_type.then(|| TypeRef {
+        name: name.to_string(),
+        ..Default::default()
+    }))
+}
+
+/// Parse every formal and varargs parameter of a `formal_parameters` node
+pub fn parse_parameters(
+    source_code: &str,
+    parameters: Node<'_>,
+) -> Result<Vec<Parameter>, Box<dyn std::error::Error>> {
+    let mut result = Vec::new();
+    let mut cursor = parameters.walk();
+    for parameter in parameters.named_children(&mut cursor) {
+        let name = match parameter.kind() {
+            "formal_parameter" => parameter.child_by_field_name("name"),
+            "spread_parameter" => {
+                let mut cursor = parameter.walk();
+                let declarator = parameter
+                    .named_children(&mut cursor)
+                    .find(|child| child.kind() == "variable_declarator");
+                declarator.and_then(|declarator| declarator.child_by_field_name("name"))
+            }
+            _ => None,
+        };
+        if let Some(name) = name {
+            result.push(Parameter {
+                name: name.utf8_text(source_code.as_bytes())?.to_string(),
+                type_ref: parse_parameter_type(source_code, parameter)?,
+                annotations: parse_annotations(source_code, parameter)?,
+                location: node_location(parameter),
+            });
+        }
+    }
+    Ok(result)
+}
+
+/// Every node of a member body in source order. Bodies of local and anonymous classes belong
+/// to those classes and are skipped.
+fn own_nodes(body: Node<'_>) -> Vec<Node<'_>> {
+    let mut nodes = Vec::new();
+    let mut pending = vec![body];
+    while let Some(node) = pending.pop() {
+        nodes.push(node);
+        // Children are pushed in reverse so they come out in source order
+        let mut cursor = node.walk();
+        let children = node.named_children(&mut cursor).filter(|child| {
+            !matches!(
+                child.kind(),
+                "class_body"
+                    | "class_declaration"
+                    | "interface_declaration"
+                    | "enum_declaration"
+                    | "record_declaration"
+            )
+        });
+        let start = pending.len();
+        pending.extend(children);
+        pending[start..].reverse();
+    }
+    nodes
+}
+
+/// Find where fields get their values in the members of a type body: `new` expressions
+/// anywhere, and parameters of constructors and methods, e.g. `this.repo = repo`
+fn parse_field_values(
+    source_code: &str,
+    body: Node<'_>,
+    fields: &mut [ClassField],
+) -> Result<(), Box<dyn std::error::Error>> {
+    for member in body_declarations(body) {
+        let parameter_value = match member.kind() {
+            "constructor_declaration" | "compact_constructor_declaration" => {
+                Some(FieldValue::ConstructorParameter)
+            }
+            "method_declaration" => Some(FieldValue::MethodParameter),
+            "block" | "static_initializer" => None, // initializers have no parameters
+            _ => continue,
+        };
+        let body = match member.kind() {
+            "block" => member,
+            "static_initializer" => match member.named_child(0) {
+                Some(body) => body,
+                None => continue,
+            },
+            _ => match member.child_by_field_name("body") {
+                Some(body) => body,
+                None => continue,
+            },
+        };
+        let parameters = match member.child_by_field_name("parameters") {
+            Some(parameters) => parse_parameters(source_code, parameters)?,
+            None => Vec::new(),
+        };
+        let is_parameter = |name: &str| parameters.iter().any(|p| p.name == name);
+        let mut locals = Vec::new();
+        for node in own_nodes(body) {
+            if node.kind() != "local_variable_declaration" {
+                continue;
+            }
+            let mut cursor = node.walk();
+            for declarator in node.children_by_field_name("declarator", &mut cursor) {
+                if let Some(name) = declarator.child_by_field_name("name") {
+                    locals.push(name.utf8_text(source_code.as_bytes())?);
+                }
+            }
+        }
+
+        for node in own_nodes(body) {
+            if node.kind() != "assignment_expression" {
+                continue;
+            }
+            let (left, right) = match (
+                node.child_by_field_name("left"),
+                node.child_by_field_name("right"),
+            ) {
+                (Some(left), Some(right)) => (left, right),
+                _ => continue,
+            };
+            // `this.repo = ...`, or `repo = ...` unless a parameter or local variable shadows
+            // the field
+            let field_name = match left.kind() {
+                "field_access" => {
+                    let object = left.child_by_field_name("object");
+                    match (object, left.child_by_field_name("field")) {
+                        (Some(object), Some(field)) if object.kind() == "this" => field,
+                        _ => continue,
+                    }
+                }
+                "identifier" => left,
+                _ => continue,
+            };
+            let field_name = field_name.utf8_text(source_code.as_bytes())?;
+            if left.kind() == "identifier"
+                && (is_parameter(field_name) || locals.contains(&field_name))
+            {
+                continue;
+            }
+            let field = match fields.iter_mut().find(|field| field.name == field_name) {
+                Some(field) => field,
+                None => continue,
+            };
+
+            let value = assigned_value(source_code, right)?;
+            let value = match (value.kind(), parameter_value) {
+                ("object_creation_expression", _) => FieldValue::Created,
+                ("identifier", Some(parameter_value))
+                    if is_parameter(value.utf8_text(source_code.as_bytes())?) =>
+                {
+                    parameter_value
+                }
+                _ => continue,
+            };
+            if !field.values.contains(&value) {
+                field.values.push(value);
+            }
+        }
+    }
+    Ok(())
+}
+
+/// The expression a value really comes from, looking through parentheses, casts and
+/// `Objects.requireNonNull(repo)`
+fn assigned_value<'a>(
+    source_code: &str,
+    node: Node<'a>,
+) -> Result<Node<'a>, Box<dyn std::error::Error>> {
+    let inner = match node.kind() {
+        "parenthesized_expression" => node.named_child(0),
+        "cast_expression" => node.child_by_field_name("value"),
+        "method_invocation" => {
+            let name = node.child_by_field_name("name");
+            let arguments = node.child_by_field_name("arguments");
+            match (name, arguments) {
+                (Some(name), Some(arguments))
+                    if name.utf8_text(source_code.as_bytes())? == "requireNonNull" =>
+                {
+                    arguments.named_child(0)
+                }
+                _ => None,
+            }
+        }
+        _ => None,
+    };
+    match inner {
+        Some(inner) => assigned_value(source_code, inner),
+        None => Ok(node),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::java_parser::parse_java_source;
+    use crate::types::{FieldValue, Modifier, Visibility};
+
+    fn field_values(source_code: &str, field_name: &str) -> Vec<FieldValue> {
+        let parsed = parse_java_source("A.java", source_code).unwrap();
+        let field = parsed.classes[0]
+            .fields
+            .iter()
+            .find(|field| field.name == field_name)
+            .unwrap();
+        field.values.clone()
+    }
+
+    #[test]
+    fn locals_shadow_fields() {
+        let source_code = "class A { C c; A(B b) { C c; c = new C(); this.c = null; } }";
+        assert_eq!(field_values(source_code, "c"), vec![]);
+    }
+
+    #[test]
+    fn parameters_assigned_to_fields() {
+        let source_code = "class A { B b; A(B b) { this.b = b; } void set(B b) { this.b = b; } }";
+        assert_eq!(
+            field_values(source_code, "b"),
+            vec![
+                FieldValue::ConstructorParameter,
+                FieldValue::MethodParameter
+            ]
+        );
+    }
+
+    #[test]
+    fn fields_created_in_initializers() {
+        let source_code =
+            "class A { private static final D D1; C c; static { D1 = new D(); } { c = new C(); } }";
+        assert_eq!(field_values(source_code, "D1"), vec![FieldValue::Created]);
+        assert_eq!(field_values(source_code, "c"), vec![FieldValue::Created]);
+    }
+
+    #[test]
+    fn visibility_and_modifiers() {
+        let source_code = "class A {
+            protected static final int MAX = 1;
+            transient volatile String cache;
+            public abstract synchronized void run();
+            private native long address();
+        }";
+        let class = &parse_java_source("A.java", source_code).unwrap().classes[0];
+        assert_eq!(class.fields[0].visibility, Visibility::Protected);
+        assert_eq!(
+            class.fields[0].modifiers,
+            [Modifier::Static, Modifier::Final]
+        );
+        assert_eq!(class.fields[1].visibility, Visibility::PackagePrivate);
+        assert_eq!(
+            class.fields[1].modifiers,
+            [Modifier::Transient, Modifier::Volatile]
+        );
+        assert_eq!(class.methods[0].visibility, Visibility::Public);
+        assert_eq!(
+            class.methods[0].modifiers,
+            [Modifier::Abstract, Modifier::Synchronized]
+        );
+        assert_eq!(class.methods[1].visibility, Visibility::Private);
+        assert_eq!(class.methods[1].modifiers, [Modifier::Native]);
+    }
+
+    #[test]
+    fn interface_members_are_implicitly_public() {
+        let source_code = "interface A {
+            int LIMIT = 10;
+            void run();
+            default void stop() {}
+            static A create() { return null; }
+            private void log() {}
+        }";
+        let class = &parse_java_source("A.java", source_code).unwrap().classes[0];
+        assert_eq!(class.fields[0].visibility, Visibility::Public);
+        assert_eq!(
+            class.fields[0].modifiers,
+            [Modifier::Static, Modifier::Final]
+        );
+        let methods = class
+            .methods
+            .iter()
+            .map(|m| (m.name.as_str(), m.visibility, m.modifiers.clone()))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            methods,
+            [
+                ("run", Visibility::Public, vec![Modifier::Abstract]),
+                ("stop", Visibility::Public, vec![Modifier::Default]),
+                ("create", Visibility::Public, vec![Modifier::Static]),
+                ("log", Visibility::Private, vec![]),
+            ]
+        );
+    }
+
+    #[test]
+    fn every_declarator_and_constructor() {
+        let source_code = "class A {
+            private int a, b = 2, values[];
+            A() {}
+            protected A(int a, String... names) throws java.io.IOException {}
+        }";
+        let class = &parse_java_source("A.java", source_code).unwrap().classes[0];
+        let fields = class
+            .fields
+            .iter()
+            .map(|f| (f.name.as_str(), f.type_ref.to_string(), f.visibility))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            fields,
+            [
+                ("a", String::from("int"), Visibility::Private),
+                ("b", String::from("int"), Visibility::Private),
+                ("values", String::from("int[]"), Visibility::Private),
+            ]
+        );
+        assert_eq!(class.constructors.len(), 2);
+        assert!(class.constructors[0].parameters.is_empty());
+        let constructor = &class.constructors[1];
+        assert_eq!(constructor.visibility, Visibility::Protected);
+        let parameters = constructor.parameters.iter().map(|p| p.name.as_str());
+        assert_eq!(parameters.collect::<Vec<_>>(), ["a", "names"]);
+        assert_eq!(constructor.throws[0].to_string(), "java.io.IOException");
+        assert!(class.methods.is_empty());
+    }
+
+    #[test]
+    fn annotations_with_their_elements() {
+        let source_code = "@Entity @Table(name = \"orders\", schema = \"shop\")
+            class Order {
+                @Id @GeneratedValue(strategy = GenerationType.AUTO) long id;
+                @Override public String toString() { return \"\"; }
+                void rename(@NotNull @Size(max = 40) String name) {}
+                @javax.annotation.Nullable @SuppressWarnings({\"a\", \"b\"}) Order(long id) {}
+            }";
+        let class = &parse_java_source("Order.java", source_code)
+            .unwrap()
+            .classes[0];
+        let names = |annotations: &[crate::types::Annotation]| {
+            let names = annotations.iter().map(|a| a.name.clone());
+            names.collect::<Vec<_>>()
+        };
+        assert_eq!(names(&class.annotations), ["Entity", "Table"]);
+        let table = &class.annotations[1].elements;
+        assert_eq!(
+            (table[1].name.as_str(), table[1].value.as_str()),
+            ("schema", "\"shop\"")
+        );
+        assert_eq!(
+            names(&class.fields[0].annotations),
+            ["Id", "GeneratedValue"]
+        );
+        assert_eq!(names(&class.methods[0].annotations), ["Override"]);
+        let parameter = &class.methods[1].parameters[0];
+        assert_eq!(names(&parameter.annotations), ["NotNull", "Size"]);
+        assert_eq!(parameter.type_ref.name, "String");
+        let constructor = &class.constructors[0].annotations;
+        assert_eq!(
+            names(constructor),
+            ["javax.annotation.Nullable", "SuppressWarnings"]
+        );
+        assert_eq!(constructor[1].elements[0].name, "value");
+        assert_eq!(constructor[1].elements[0].value, "{\"a\", \"b\"}");
+    }
+}
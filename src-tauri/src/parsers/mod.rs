@@ -1,28 +1,45 @@
-use tree_sitter::{Parser, Query, QueryCursor};
+use std::sync::OnceLock;
 
+use tree_sitter::{Node, Query, QueryCursor, QueryMatch};
+
+use crate::types::{Import, SourceLocation};
+
+pub mod annotation_parser;
 pub mod class_parser;
 pub mod enum_parser;
 pub mod interface_parser;
+pub mod member_parser;
 pub mod record_parser;
+pub mod type_parser;
 
-fn extract_package(
+static PACKAGE_QUERY: OnceLock<Query> = OnceLock::new();
+
+/// Compile a query the first time it's needed and share it between files and threads
+fn shared_query(
+    cell: &'static OnceLock<Query>,
+    source: &str,
+) -> Result<&'static Query, Box<dyn std::error::Error>> {
+    if let Some(query) = cell.get() {
+        return Ok(query);
+    }
+    let query = Query::new(tree_sitter_java::language(), source)?;
+    Ok(cell.get_or_init(|| query))
+}
+
+pub fn extract_package(
     source_code: &str,
     root_node: tree_sitter::Node,
 ) -> Result<String, Box<dyn std::error::Error>> {
-    let mut parser = Parser::new();
-    let language = tree_sitter_java::language();
-    parser.set_language(language)?;
-
-    let package_query = Query::new(
-        language,
+    let package_query = shared_query(
+        &PACKAGE_QUERY,
         "
     (package_declaration
-        (scoped_identifier) @package)
+        [(identifier) (scoped_identifier)] @package)
         ",
     )?;
 
     let mut query_cursor = QueryCursor::new();
-    let matches = query_cursor.matches(&package_query, root_node, source_code.as_bytes());
+    let matches = query_cursor.matches(package_query, root_node, source_code.as_bytes());
 
     for match_ in matches {
         if let Some(capture) = match_.captures.first() {
@@ -34,3 +51,87 @@ fn extract_package(
 
     Ok(String::new())
 }
+
+/// Span of a node, the file is filled in by the caller that knows it
+pub fn node_location(node: Node<'_>) -> SourceLocation {
+    let start = node.start_position();
+    let end = node.end_position();
+    SourceLocation {
+        file: String::new(),
+        start_byte: node.start_byte(),
+        end_byte: node.end_byte(),
+        start_line: start.row + 1,
+        start_column: start.column + 1,
+        end_line: end.row + 1,
+        end_column: end.column + 1,
+    }
+}
+
+/// Extract the single-type, wildcard and static imports of a file
+pub fn extract_imports(
+    source_code: &str,
+    root_node: tree_sitter::Node,
+) -> Result<Vec<Import>, Box<dyn std::error::Error>> {
+    let mut imports = Vec::new();
+    let mut cursor = root_node.walk();
+    for child in root_node.named_children(&mut cursor) {
+        if child.kind() != "import_declaration" {
+            continue;
+        }
+        let mut import = Import {
+            path: String::new(),
+            is_static: false,
+            wildcard: false,
+        };
+        let mut import_cursor = child.walk();
+        for part in child.children(&mut import_cursor) {
+            match part.kind() {
+                "static" => import.is_static = true,
+                "asterisk" => import.wildcard = true,
+                "identifier" | "scoped_identifier" => {
+                    import.path = part.utf8_text(source_code.as_bytes())?.to_string()
+                }
+                _ => {}
+            }
+        }
+        imports.push(import);
+    }
+    Ok(imports)
+}
+
+/// Run a header query on a type declaration and pass every capture of the matches `node`
+/// produced itself to `f`, with its capture name
+fn for_each_own_capture<'a>(
+    query: &Query,
+    source_code: &str,
+    node: Node<'a>,
+    name_capture: &str,
+    mut f: impl FnMut(&str, Node<'a>) -> Result<(), Box<dyn std::error::Error>>,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let mut cursor = QueryCursor::new();
+    let matches = cursor.matches(query, node, source_code.as_bytes());
+    // Captures are only valid while the cursor sits on their match, so don't collect them
+    for match_ in matches {
+        if !is_own_match(query, &match_, name_capture, node) {
+            continue;
+        }
+        for capture in match_.captures {
+            f(&query.capture_names()[capture.index as usize], capture.node)?;
+        }
+    }
+    Ok(())
+}
+
+/// Whether a header query match was produced by `node` itself rather than by a type nested
+/// inside it, judged by the parent of the capture holding the declaration name.
+fn is_own_match(
+    query: &Query,
+    match_: &QueryMatch<'_, '_>,
+    name_capture: &str,
+    node: Node<'_>,
+) -> bool {
+    match_.captures.iter().any(|capture| {
+        query.capture_names()[capture.index as usize] == name_capture
+            && capture.node.parent() == Some(node)
+    })
+}
@@ -1,129 +1,156 @@
-use tree_sitter::{Node, Query, QueryCursor};
+use std::sync::OnceLock;
 
-use crate::types::{ClassField, ClassInfo, ClassType};
+use tree_sitter::{Node, Query};
 
-use super::extract_package;
+use crate::types::{ClassField, ClassInfo, ClassType, Modifier, Parameter, Visibility};
 
-/// Creates a tree-sitter query for Java record declarations
-fn create_record_query() -> Result<Query, Box<dyn std::error::Error>> {
-    let language = tree_sitter_java::language();
-    Query::new(
-        language,
+use super::member_parser::{body_declarations, parse_members, parse_parameters};
+use super::type_parser::{parse_type, parse_type_parameters};
+use super::{for_each_own_capture, shared_query};
+
+static RECORD_QUERY: OnceLock<Query> = OnceLock::new();
+
+/// The tree-sitter query for Java record declarations
+fn record_query() -> Result<&'static Query, Box<dyn std::error::Error>> {
+    shared_query(
+        &RECORD_QUERY,
         "
         (record_declaration
             name: (identifier) @record-name
-            parameters: (formal_parameters
-                (formal_parameter
-                    type: (_) @field-type
-                    name: (identifier)
-                )+
-            ) @record-fields
+            type_parameters: (type_parameters)? @type-parameters
+            parameters: (formal_parameters) @record-fields
+            interfaces: (super_interfaces
+                (type_list (_) @implements))?
         )",
     )
-    .map_err(|e| e.into())
 }
 
 /// Initialize a ClassInfo structure for a Java record
-fn init_class_info(
-    source_code: &str,
-    root_node: Node<'_>,
-) -> Result<ClassInfo, Box<dyn std::error::Error>> {
-    Ok(ClassInfo {
-        name: String::new(),
-        package: extract_package(source_code, root_node)?,
-        fields: Vec::new(),
-        methods: Vec::new(),
-        extends: None,
-        implements: Vec::new(),
-        class_type: ClassType::Record,
-    })
+fn init_class_info() -> ClassInfo {
+    ClassInfo::new(ClassType::Record)
 }
 
-/// Extract record name from a capture node
+/// Extract record name from a captured name node
 fn extract_record_name(
-    capture: &tree_sitter::QueryCapture,
+    name: Node<'_>,
     source_code: &str,
 ) -> Result<String, Box<dyn std::error::Error>> {
-    Ok(capture.node.utf8_text(source_code.as_bytes())?.to_string())
+    Ok(name.utf8_text(source_code.as_bytes())?.to_string())
 }
 
-/// Process record fields from a capture node
-fn process_record_fields(
-    capture: &tree_sitter::QueryCapture,
-    source_code: &str,
-) -> Result<Vec<ClassField>, Box<dyn std::error::Error>> {
-    let mut fields = Vec::new();
-    let mut index = 0;
-
-    while index < capture.node.child_count() {
-        let child = capture.node.child(index).unwrap();
-        if child.kind() == "formal_parameter" {
-            if let Some(field) = extract_field_from_parameter(child, source_code)? {
-                fields.push(field);
-            }
-        }
-        index += 1;
-    }
-
-    Ok(fields)
-}
-
-/// Extract a field from a formal parameter node
-fn extract_field_from_parameter(
-    param_node: Node,
-    source_code: &str,
-) -> Result<Option<ClassField>, Box<dyn std::error::Error>> {
-    let mut i = 0;
-    while i < param_node.child_count() {
-        let child = param_node.child(i).unwrap();
-        if child.kind() == "type_identifier" {
-            let field_type = child.utf8_text(source_code.as_bytes())?.to_string();
-            let field_name_node = child.next_sibling().unwrap();
-            let field_name = field_name_node.utf8_text(source_code.as_bytes())?;
-
-            return Ok(Some(ClassField {
-                name: field_name.to_string(),
-                type_name: field_type,
-                visibility: String::new(),
-            }));
-        }
-        i += 1;
-    }
-
-    Ok(None)
+/// Turn record components into the private final fields they declare
+fn components_to_fields(components: &[Parameter]) -> Vec<ClassField> {
+    components
+        .iter()
+        .map(|component| ClassField {
+            name: component.name.to_owned(),
+            type_ref: component.type_ref.clone(),
+            visibility: Visibility::Private,
+            modifiers: vec![Modifier::Final],
+            annotations: component.annotations.clone(),
+            values: Vec::new(),
+            location: component.location.clone(),
+        })
+        .collect()
 }
 
 /// Parse a Java record from source code
 pub fn parse_java_record(
     source_code: &str,
-    root_node: Node<'_>,
+    node: Node<'_>,
 ) -> Result<ClassInfo, Box<dyn std::error::Error>> {
-    // Create query and cursor
-    let query = create_record_query()?;
-    let mut cursor = QueryCursor::new();
-    let matches = cursor.matches(&query, root_node, source_code.as_bytes());
-    let class_matches = matches.collect::<Vec<_>>();
-
-    // Initialize class info
-    let mut class_info = init_class_info(source_code, root_node)?;
-    let capture_names = query.capture_names();
-
-    // Process matches
-    let match_option = class_matches.first();
-    if match_option.is_none() {
-        return Ok(class_info);
-    }
-    let match_ = match_option.unwrap();
-    for capture in match_.captures {
-        let capture_index = capture.index as usize;
-        let capture_name = &capture_names[capture_index];
-
-        match capture_name.as_str() {
-            "record-name" => class_info.name = extract_record_name(&capture, source_code)?,
-            "record-fields" => class_info.fields = process_record_fields(&capture, source_code)?,
+    let query = record_query()?;
+    let mut class_info = init_class_info();
+    let mut components = Vec::new();
+
+    // There is one match per implemented interface
+    for_each_own_capture(query, source_code, node, "record-name", |name, capture| {
+        match name {
+            "record-name" => class_info.name = extract_record_name(capture, source_code)?,
+            "type-parameters" => {
+                class_info.type_parameters = parse_type_parameters(source_code, capture)?
+            }
+            "record-fields" => components = parse_parameters(source_code, capture)?,
+            "implements" => class_info
+                .implements
+                .push(parse_type(source_code, capture)?),
             _ => {}
         }
+        Ok(())
+    })?;
+    class_info.fields = components_to_fields(&components);
+
+    // Parse static fields, constructors and methods declared in the body
+    let body = match node.child_by_field_name("body") {
+        Some(body) => body,
+        None => return Ok(class_info),
+    };
+    parse_members(source_code, body, &mut class_info)?;
+
+    // Compact constructors take the record components implicitly
+    let constructor_nodes = body_declarations(body)
+        .into_iter()
+        .filter(|member| {
+            member.kind() == "constructor_declaration"
+                || member.kind() == "compact_constructor_declaration"
+        })
+        .collect::<Vec<_>>();
+    for (constructor, constructor_node) in class_info.constructors.iter_mut().zip(constructor_nodes)
+    {
+        if constructor_node.kind() == "compact_constructor_declaration" {
+            constructor.parameters = components.clone();
+        }
     }
 
     Ok(class_info)
 }
+
+#[cfg(test)]
+mod tests {
+    use crate::java_parser::parse_java_source;
+    use crate::types::{ClassType, Modifier};
+
+    #[test]
+    fn components_compact_constructor_and_members() {
+        let source_code = "record Pair<T>(T first, int[] counts, java.util.List<T> rest)
+                implements Comparable<Pair<T>>, java.io.Serializable {
+            static final Pair<?> EMPTY = null;
+            Pair { java.util.Objects.requireNonNull(first); }
+            Pair(T first) { this(first, new int[0], java.util.List.of()); }
+            public int compareTo(Pair<T> other) { return 0; }
+        }";
+        let class = &parse_java_source("Pair.java", source_code).unwrap().classes[0];
+        assert!(matches!(class.class_type, ClassType::Record));
+        assert_eq!(class.type_parameters[0].name, "T");
+        let implements = class.implements.iter().map(|t| t.to_string());
+        assert_eq!(
+            implements.collect::<Vec<_>>(),
+            ["Comparable<Pair<T>>", "java.io.Serializable"]
+        );
+
+        let fields = class
+            .fields
+            .iter()
+            .map(|f| (f.name.as_str(), f.type_ref.to_string()))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            fields,
+            [
+                ("first", String::from("T")),
+                ("counts", String::from("int[]")),
+                ("rest", String::from("java.util.List<T>")),
+                ("EMPTY", String::from("Pair<?>")),
+            ]
+        );
+        assert_eq!(class.fields[0].modifiers, [Modifier::Final]);
+
+        // The compact constructor takes every component, the other one only what it declares
+        let parameters = |i: usize| {
+            let parameters = class.constructors[i].parameters.iter();
+            parameters.map(|p| p.name.as_str()).collect::<Vec<_>>()
+        };
+        assert_eq!(parameters(0), ["first", "counts", "rest"]);
+        assert_eq!(parameters(1), ["first"]);
+        assert_eq!(class.methods[0].name, "compareTo");
+    }
+}
@@ -0,0 +1,278 @@
+use tree_sitter::Node;
+
+use crate::types::{TypeArgument, TypeParameter, TypeRef};
+
+const PRIMITIVE_TYPES: [&str; 4] = [
+    "integral_type",
+    "floating_point_type",
+    "boolean_type",
+    "void_type",
+];
+
+/// Build a structured type reference from any tree-sitter type node
+pub fn parse_type(
+    source_code: &str,
+    node: Node<'_>,
+) -> Result<TypeRef, Box<dyn std::error::Error>> {
+    let kind = node.kind();
+    if PRIMITIVE_TYPES.contains(&kind) {
+        return Ok(TypeRef {
+            name: node.utf8_text(source_code.as_bytes())?.to_string(),
+            primitive: true,
+            ..Default::default()
+        });
+    }
+
+    match kind {
+        "scoped_type_identifier" => {
+            // The last type_identifier is the name, everything before the final dot qualifies it
+            let mut type_ref = TypeRef::default();
+            let mut cursor = node.walk();
+            let parts = node
+                .named_children(&mut cursor)
+                .filter(|child| child.kind() != "marker_annotation" && child.kind() != "annotation")
+                .collect::<Vec<_>>();
+            if let Some((name, qualifier)) = parts.split_last() {
+                type_ref.name = name.utf8_text(source_code.as_bytes())?.to_string();
+                if let Some(qualifier) = qualifier.first() {
+                    type_ref.qualifier =
+                        Some(qualifier.utf8_text(source_code.as_bytes())?.to_string());
+                }
+            }
+            Ok(type_ref)
+        }
+        "generic_type" => {
+            let mut type_ref = TypeRef::default();
+            let mut cursor = node.walk();
+            for child in node.named_children(&mut cursor) {
+                if child.kind() == "type_arguments" {
+                    type_ref.arguments = parse_type_arguments(source_code, child)?;
+                } else {
+                    let base = parse_type(source_code, child)?;
+                    type_ref.name = base.name;
+                    type_ref.qualifier = base.qualifier;
+                }
+            }
+            Ok(type_ref)
+        }
+        "array_type" => {
+            let mut type_ref = match node.child_by_field_name("element") {
+                Some(element) => parse_type(source_code, element)?,
+                None => TypeRef::default(),
+            };
+            if let Some(dimensions) = node.child_by_field_name("dimensions") {
+                type_ref.array_dimensions += count_dimensions(dimensions);
+            }
+            Ok(type_ref)
+        }
+        "annotated_type" => {
+            // Type annotations don't change the type itself, parse what they annotate
+            let mut cursor = node.walk();
+            let inner = node
+                .named_children(&mut cursor)
+                .filter(|child| child.kind() != "marker_annotation" && child.kind() != "annotation")
+                .last();
+            match inner {
+                Some(inner) => parse_type(source_code, inner),
+                None => Ok(TypeRef::default()),
+            }
+        }
+        _ => Ok(TypeRef {
+            name: node.utf8_text(source_code.as_bytes())?.to_string(),
+            ..Default::default()
+        }),
+    }
+}
+
+/// Parse the type of a `formal_parameter` or `spread_parameter`, including C-style
+/// `String args[]` dimensions and varargs
+pub fn parse_parameter_type(
+    source_code: &str,
+    parameter: Node<'_>,
+) -> Result<TypeRef, Box<dyn std::error::Error>> {
+    if parameter.kind() == "spread_parameter" {
+        let mut cursor = parameter.walk();
+        let type_node = parameter
+            .named_children(&mut cursor)
+            .find(|child| child.kind() != "modifiers" && child.kind() != "variable_declarator");
+        let mut type_ref = match type_node {
+            Some(type_node) => parse_type(source_code, type_node)?,
+            None => TypeRef::default(),
+        };
+        type_ref.varargs = true;
+        type_ref.array_dimensions += 1;
+        return Ok(type_ref);
+    }
+
+    let mut type_ref = match parameter.child_by_field_name("type") {
+        Some(type_node) => parse_type(source_code, type_node)?,
+        None => TypeRef::default(),
+    };
+    if let Some(dimensions) = parameter.child_by_field_name("dimensions") {
+        type_ref.array_dimensions += count_dimensions(dimensions);
+    }
+    Ok(type_ref)
+}
+
+/// Number of `[]` pairs in a `dimensions` node
+pub fn count_dimensions(dimensions: Node<'_>) -> usize {
+    dimensions
+        .children(&mut dimensions.walk())
+        .filter(|child| child.kind() == "[")
+        .count()
+}
+
+fn parse_type_arguments(
+    source_code: &str,
+    node: Node<'_>,
+) -> Result<Vec<TypeArgument>, Box<dyn std::error::Error>> {
+    let mut arguments = Vec::new();
+    let mut cursor = node.walk();
+    for child in node.named_children(&mut cursor) {
+        if child.kind() == "wildcard" {
+            arguments.push(parse_wildcard(source_code, child)?);
+        } else {
+            arguments.push(TypeArgument::Type(parse_type(source_code, child)?));
+        }
+    }
+    Ok(arguments)
+}
+
+fn parse_wildcard(
+    source_code: &str,
+    node: Node<'_>,
+) -> Result<TypeArgument, Box<dyn std::error::Error>> {
+    let mut cursor = node.walk();
+    let children = node.children(&mut cursor).collect::<Vec<_>>();
+    let bound_keyword = children
+        .iter()
+        .position(|child| child.kind() == "extends" || child.kind() == "super");
+    let bound_keyword = match bound_keyword {
+        Some(index) => index,
+        None => return Ok(TypeArgument::Wildcard),
+    };
+
+    let bound = match children[bound_keyword + 1..]
+        .iter()
+        .find(|child| child.is_named())
+    {
+        Some(bound) => parse_type(source_code, *bound)?,
+        None => return Ok(TypeArgument::Wildcard),
+    };
+    if children[bound_keyword].kind() == "extends" {
+        Ok(TypeArgument::Extends(bound))
+    } else {
+        Ok(TypeArgument::Super(bound))
+    }
+}
+
+/// Parse a `type_parameters` node such as `<T extends Number & Comparable<T>, K>`
+pub fn parse_type_parameters(
+    source_code: &str,
+    node: Node<'_>,
+) -> Result<Vec<TypeParameter>, Box<dyn std::error::Error>> {
+    let mut type_parameters = Vec::new();
+    let mut cursor = node.walk();
+    for parameter in node.named_children(&mut cursor) {
+        if parameter.kind() != "type_parameter" {
+            continue;
+        }
+        let mut type_parameter = TypeParameter {
+            name: String::new(),
+            bounds: Vec::new(),
+        };
+        let mut parameter_cursor = parameter.walk();
+        for child in parameter.named_children(&mut parameter_cursor) {
+            match child.kind() {
+                "type_identifier" => {
+                    type_parameter.name = child.utf8_text(source_code.as_bytes())?.to_string()
+                }
+                "type_bound" => {
+                    let mut bound_cursor = child.walk();
+                    for bound in child.named_children(&mut bound_cursor) {
+                        type_parameter.bounds.push(parse_type(source_code, bound)?);
+                    }
+                }
+                _ => {}
+            }
+        }
+        type_parameters.push(type_parameter);
+    }
+    Ok(type_parameters)
+}
+
+/// Parse the types of a `type_list`, as found in `permits` and `implements` clauses
+pub fn parse_type_list(
+    source_code: &str,
+    node: Node<'_>,
+) -> Result<Vec<TypeRef>, Box<dyn std::error::Error>> {
+    let mut types = Vec::new();
+    let mut cursor = node.walk();
+    for child in node.named_children(&mut cursor) {
+        if child.kind() == "type_list" {
+            types.extend(parse_type_list(source_code, child)?);
+        } else {
+            types.push(parse_type(source_code, child)?);
+        }
+    }
+    Ok(types)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::java_parser::parse_java_source;
+    use crate::types::{TypeArgument, TypeRef};
+
+    fn field_types(source_code: &str) -> Vec<TypeRef> {
+        let classes = parse_java_source("A.java", source_code).unwrap().classes;
+        classes[0]
+            .fields
+            .iter()
+            .map(|field| field.type_ref.clone())
+            .collect()
+    }
+
+    fn simple(name: &str) -> TypeRef {
+        TypeRef {
+            name: name.to_string(),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn generic_qualified_and_array_types() {
+        let types = field_types(
+            "class A {
+                Map<String, Customer[]> byName;
+                java.util.List<? extends Order> orders;
+                Map.Entry<?, ? super Order> entry;
+                int[][] grid;
+            }",
+        );
+        let mut customers = simple("Customer");
+        customers.array_dimensions = 1;
+        assert_eq!(types[0].name, "Map");
+        assert_eq!(
+            types[0].arguments,
+            [
+                TypeArgument::Type(simple("String")),
+                TypeArgument::Type(customers)
+            ]
+        );
+        assert_eq!(types[1].qualifier.as_deref(), Some("java.util"));
+        assert_eq!(types[1].arguments, [TypeArgument::Extends(simple("Order"))]);
+        assert_eq!(types[2].to_string(), "Map.Entry<?, ? super Order>");
+        assert!(types[3].primitive);
+        assert_eq!(types[3].array_dimensions, 2);
+    }
+
+    #[test]
+    fn varargs_and_c_style_array_parameters() {
+        let source_code = "class A { void run(String... names, int codes[]) {} }";
+        let classes = parse_java_source("A.java", source_code).unwrap().classes;
+        let parameters = &classes[0].methods[0].parameters;
+        assert!(parameters[0].type_ref.varargs);
+        assert_eq!(parameters[0].type_ref.to_string(), "String...");
+        assert_eq!(parameters[1].type_ref.to_string(), "int[]");
+    }
+}
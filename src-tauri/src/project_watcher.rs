@@ -0,0 +1,301 @@
+use std::collections::{BTreeSet, HashMap};
+use std::path::Path;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{mpsc, Mutex, MutexGuard};
+use std::time::Duration;
+
+use log::warn;
+use notify::event::ModifyKind;
+use notify::{Event, EventKind, RecommendedWatcher, RecursiveMode, Watcher};
+use tauri::{AppHandle, Emitter};
+use tree_sitter::{InputEdit, Point, Tree};
+
+use crate::commands::{project_cache_path, read_structure_from_path, IGNORED_FOLDERS};
+use crate::java_parser::parse_java_source_with;
+use crate::parse_cache::{content_hash, with_cache};
+use crate::symbol_index::update_project_index;
+use crate::types::{ParsedFile, Project, ProjectChange};
+
+/// Editors often write a file in several steps, changes are handled once they settle
+const SETTLE_TIME: Duration = Duration::from_millis(200);
+
+/// The project open in the app, only one is watched at a time
+static WATCHED: Mutex<Option<Watched>> = Mutex::new(None);
+static RUNS: AtomicU64 = AtomicU64::new(0);
+
+struct Watched {
+    project_id: u16,
+    run: u64, // tells a thread's own watcher from one that replaced it
+    _watcher: RecommendedWatcher,
+}
+
+/// Clears the watched project when its thread ends, unless another one replaced it
+struct ClearOnExit(u64);
+
+impl Drop for ClearOnExit {
+    fn drop(&mut self) {
+        let mut watched = watched();
+        if watched
+            .as_ref()
+            .is_some_and(|watched| watched.run == self.0)
+        {
+            *watched = None;
+        }
+    }
+}
+
+fn watched() -> MutexGuard<'static, Option<Watched>> {
+    WATCHED
+        .lock()
+        .unwrap_or_else(|poisoned| poisoned.into_inner())
+}
+
+/// Last parsed content and tree of the files that changed while watched, to parse them
+/// incrementally next time
+type Trees = HashMap<String, (String, Tree)>;
+
+/// Watch a project folder instead of the one watched before, unless it's watched already.
+/// Changed Java files are parsed again into the parse cache and symbol index, then the
+/// frontend gets a `project-changed` event.
+pub fn watch_project(app: AppHandle, project: Project) -> Result<(), Box<dyn std::error::Error>> {
+    let mut watched = watched();
+    if watched
+        .as_ref()
+        .is_some_and(|watched| watched.project_id == project.id)
+    {
+        return Ok(());
+    }
+    // Dropping the previous watcher ends its thread
+    *watched = None;
+
+    let (sender, receiver) = mpsc::channel();
+    let mut watcher = notify::recommended_watcher(sender)?;
+    watcher.watch(Path::new(&project.path), RecursiveMode::Recursive)?;
+    let run = RUNS.fetch_add(1, Ordering::Relaxed);
+    *watched = Some(Watched {
+        project_id: project.id,
+        run,
+        _watcher: watcher,
+    });
+
+    // Runs until the watcher and with it the sender are dropped
+    std::thread::spawn(move || {
+        let _clear = ClearOnExit(run);
+        let mut trees = Trees::new();
+        while let Ok(first) = receiver.recv() {
+            let mut events = vec![first];
+            while let Ok(event) = receiver.recv_timeout(SETTLE_TIME) {
+                events.push(event);
+            }
+            if let Some(change) = handle_events(&project, &mut trees, events) {
+                if let Err(e) = app.emit("project-changed", change) {
+                    warn!("Failed to emit project-changed: {}", e);
+                }
+            }
+        }
+    });
+    Ok(())
+}
+
+fn handle_events(
+    project: &Project,
+    trees: &mut Trees,
+    events: Vec<notify::Result<Event>>,
+) -> Option<ProjectChange> {
+    let mut changed = BTreeSet::new();
+    let mut removed = BTreeSet::new();
+    let mut structure_changed = false;
+
+    for event in events {
+        let event = match event {
+            Ok(event) => event,
+            Err(e) => {
+                warn!("Watching {} failed: {}", project.path, e);
+                continue;
+            }
+        };
+        let adds_or_removes = matches!(
+            event.kind,
+            EventKind::Create(_) | EventKind::Remove(_) | EventKind::Modify(ModifyKind::Name(_))
+        );
+        for path in event.paths {
+            if is_ignored(&project.path, &path) {
+                continue;
+            }
+            let is_java = path
+                .extension()
+                .is_some_and(|extension| extension == "java");
+            // A removed folder can't be looked at anymore, folders rarely have an extension
+            let is_folder = path.is_dir() || (!path.exists() && path.extension().is_none());
+            structure_changed |= adds_or_removes && (is_java || is_folder);
+            if is_java {
+                // The last event of a path wins, a file can be removed and written again
+                let path = path.to_string_lossy().into_owned();
+                if Path::new(&path).exists() {
+                    removed.remove(&path);
+                    changed.insert(path);
+                } else {
+                    changed.remove(&path);
+                    removed.insert(path);
+                }
+            }
+        }
+    }
+
+    let mut reparsed = Vec::new();
+    for path in changed {
+        match reparse(&path, trees) {
+            Ok(Some((hash, parsed))) => reparsed.push((path, hash, parsed)),
+            Ok(None) => {}
+            Err(e) => warn!("Failed to parse {}: {}", path, e),
+        }
+    }
+    for path in &removed {
+        trees.remove(path);
+    }
+    if reparsed.is_empty() && removed.is_empty() && !structure_changed {
+        return None;
+    }
+
+    with_cache(&project_cache_path(project), |cache| {
+        for (path, hash, parsed) in &reparsed {
+            cache.insert(path.to_owned(), *hash, parsed.clone());
+        }
+        for path in &removed {
+            cache.remove(path);
+        }
+    });
+    update_project_index(project.id, |index| {
+        for (path, _, parsed) in &reparsed {
+            index.update_file(path, &parsed.classes);
+        }
+        for path in &removed {
+            index.remove_file(path);
+        }
+    });
+
+    let structure = if structure_changed && Path::new(&project.path).is_dir() {
+        read_structure_from_path(&project.path).unwrap_or_else(|e| {
+            warn!("Failed to read {}: {}", project.path, e);
+            None
+        })
+    } else {
+        None
+    };
+    Some(ProjectChange {
+        project_id: project.id,
+        changed_files: reparsed.into_iter().map(|(path, _, _)| path).collect(),
+        removed_files: removed.into_iter().collect(),
+        structure,
+    })
+}
+
+/// Whether a path is inside one of the folders the file tree leaves out
+fn is_ignored(project_path: &str, path: &Path) -> bool {
+    let relative = path.strip_prefix(project_path).unwrap_or(path);
+    relative.components().any(|component| {
+        let name = component.as_os_str().to_string_lossy();
+        IGNORED_FOLDERS.contains(&name.as_ref())
+    })
+}
+
+/// Parse a changed file again, incrementally when its previous tree is known.
+/// None when its content is the same as last time.
+fn reparse(
+    path: &str,
+    trees: &mut Trees,
+) -> Result<Option<(u64, ParsedFile)>, Box<dyn std::error::Error>> {
+    let source_code = std::fs::read_to_string(path)?;
+    let previous = match trees.get_mut(path) {
+        Some((old_source, _)) if *old_source == source_code => return Ok(None),
+        Some((old_source, tree)) => {
+            tree.edit(&source_edit(old_source, &source_code));
+            Some(&*tree)
+        }
+        None => None,
+    };
+    let (parsed, tree) = parse_java_source_with(path, &source_code, previous)?;
+    let hash = content_hash(&source_code);
+    trees.insert(path.to_string(), (source_code, tree));
+    Ok(Some((hash, parsed)))
+}
+
+/// The edit turning `old` into `new`: everything between their common prefix and suffix
+fn source_edit(old: &str, new: &str) -> InputEdit {
+    let (old_bytes, new_bytes) = (old.as_bytes(), new.as_bytes());
+    let prefix = old_bytes
+        .iter()
+        .zip(new_bytes)
+        .take_while(|(a, b)| a == b)
+        .count();
+    let suffix = old_bytes[prefix..]
+        .iter()
+        .rev()
+        .zip(new_bytes[prefix..].iter().rev())
+        .take_while(|(a, b)| a == b)
+        .count();
+    InputEdit {
+        start_byte: prefix,
+        old_end_byte: old.len() - suffix,
+        new_end_byte: new.len() - suffix,
+        start_position: point_at(old_bytes, prefix),
+        old_end_position: point_at(old_bytes, old.len() - suffix),
+        new_end_position: point_at(new_bytes, new.len() - suffix),
+    }
+}
+
+/// Row and byte column of a byte offset
+fn point_at(text: &[u8], byte: usize) -> Point {
+    let before = &text[..byte];
+    let row = before.iter().filter(|b| **b == b'\n').count();
+    let line_start = before
+        .iter()
+        .rposition(|b| *b == b'\n')
+        .map_or(0, |i| i + 1);
+    Point {
+        row,
+        column: byte - line_start,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn source_edit_spans_the_changed_text() {
+        let old = "class A {\n    int a;\n}\n";
+        let new = "class A {\n    long b;\n}\n";
+        let edit = source_edit(old, new);
+        assert_eq!(edit.start_byte, 14);
+        assert_eq!(edit.old_end_byte, 19);
+        assert_eq!(edit.new_end_byte, 20);
+        assert_eq!(edit.start_position, Point { row: 1, column: 4 });
+        assert_eq!(edit.old_end_position, Point { row: 1, column: 9 });
+        assert_eq!(edit.new_end_position, Point { row: 1, column: 10 });
+
+        // Text appended at the end keeps the whole old text as prefix
+        let edit = source_edit("class A {}", "class A {}\nclass B {}");
+        assert_eq!(
+            (edit.start_byte, edit.old_end_byte, edit.new_end_byte),
+            (10, 10, 21)
+        );
+        assert_eq!(edit.new_end_position, Point { row: 1, column: 10 });
+    }
+
+    #[test]
+    fn ignored_folders_anywhere_below_the_project() {
+        let project = "/work/shop";
+        assert!(is_ignored(project, Path::new("/work/shop/target/A.java")));
+        assert!(is_ignored(
+            project,
+            Path::new("/work/shop/app/node_modules/B.java")
+        ));
+        assert!(!is_ignored(project, Path::new("/work/shop/src/Order.java")));
+        // Only the part below the project counts
+        assert!(!is_ignored(
+            "/work/target/shop",
+            Path::new("/work/target/shop/A.java")
+        ));
+    }
+}
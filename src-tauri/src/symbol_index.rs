@@ -0,0 +1,334 @@
+use std::collections::{BTreeSet, HashMap, HashSet};
+use std::sync::{Arc, Mutex, MutexGuard, OnceLock};
+
+use serde::{Deserialize, Serialize};
+
+use crate::types::{ClassInfo, ClassType, SourceLocation};
+
+static INDEXES: OnceLock<Mutex<HashMap<u16, Arc<SymbolIndex>>>> = OnceLock::new();
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq)]
+pub enum SymbolKind {
+    Package,
+    Class,
+    Interface,
+    Enum,
+    Record,
+    Annotation,
+    EnumConstant,
+    Field,
+    Method,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct Symbol {
+    pub name: String,           // e.g. "OrderService", "Order.Line" or "save"
+    pub qualified_name: String, // e.g. "com.shop.OrderService.save"
+    pub kind: SymbolKind,
+    pub container: Option<String>, // declaring type of a member, package of a type
+    pub location: Option<SourceLocation>, // packages are spread over many files
+}
+
+impl SymbolKind {
+    fn is_member(self) -> bool {
+        matches!(
+            self,
+            SymbolKind::EnumConstant | SymbolKind::Field | SymbolKind::Method
+        )
+    }
+}
+
+/// Every package, type and member of a project, searchable by abbreviation
+#[derive(Debug, Default, Clone)]
+pub struct SymbolIndex {
+    files: HashMap<String, Vec<Symbol>>, // types and members by declaring file
+    packages: Vec<Symbol>,
+}
+
+impl SymbolIndex {
+    pub fn build(classes: &[ClassInfo]) -> SymbolIndex {
+        let mut index = SymbolIndex::default();
+        for class in classes {
+            let symbols = index.files.entry(class.location.file.clone()).or_default();
+            symbols.extend(class_symbols(class));
+        }
+        index.update_packages();
+        index
+    }
+
+    /// Replace the symbols of a file that was parsed again
+    pub fn update_file(&mut self, file_path: &str, classes: &[ClassInfo]) {
+        let symbols = classes.iter().flat_map(class_symbols).collect();
+        self.files.insert(file_path.to_string(), symbols);
+        self.update_packages();
+    }
+
+    pub fn remove_file(&mut self, file_path: &str) {
+        if self.files.remove(file_path).is_some() {
+            self.update_packages();
+        }
+    }
+
+    /// Qualified names of every type of the project
+    pub fn type_names(&self) -> HashSet<String> {
+        let types = self.files.values().flatten();
+        let types = types.filter(|symbol| !symbol.kind.is_member());
+        types.map(|symbol| symbol.qualified_name.clone()).collect()
+    }
+
+    /// Packages are listed once, however many files declare types in them
+    fn update_packages(&mut self) {
+        let packages = self
+            .files
+            .values()
+            .flatten()
+            .filter(|symbol| !symbol.kind.is_member())
+            .filter_map(|symbol| symbol.container.clone())
+            .collect::<BTreeSet<_>>();
+        self.packages = packages
+            .into_iter()
+            .map(|package| Symbol {
+                name: package.clone(),
+                qualified_name: package,
+                kind: SymbolKind::Package,
+                container: None,
+                location: None,
+            })
+            .collect();
+    }
+
+    /// Types and packages matching `query` first, then members. Within each, the best matches
+    /// come first and shorter names before longer ones.
+    pub fn search(&self, query: &str, limit: usize) -> Vec<Symbol> {
+        if query.is_empty() {
+            return Vec::new();
+        }
+        let mut matches = self
+            .files
+            .values()
+            .flatten()
+            .chain(&self.packages)
+            .filter_map(|symbol| {
+                let score = fuzzy_score(query, &symbol.name)?;
+                Some((score, symbol))
+            })
+            .collect::<Vec<_>>();
+        matches.sort_by(|(a_score, a), (b_score, b)| {
+            a.kind
+                .is_member()
+                .cmp(&b.kind.is_member())
+                .then(b_score.cmp(a_score))
+                .then(a.name.len().cmp(&b.name.len()))
+                .then(a.qualified_name.cmp(&b.qualified_name))
+        });
+        matches
+            .into_iter()
+            .take(limit)
+            .map(|(_, symbol)| symbol.clone())
+            .collect()
+    }
+}
+
+/// The type itself and its enum constants, fields and methods
+fn class_symbols(class: &ClassInfo) -> Vec<Symbol> {
+    let qualified_name = class.qualified_name();
+    let kind = match class.class_type {
+        ClassType::Class | ClassType::AbstractClass => SymbolKind::Class,
+        ClassType::Interface => SymbolKind::Interface,
+        ClassType::Enum => SymbolKind::Enum,
+        ClassType::Record => SymbolKind::Record,
+        ClassType::Annotation => SymbolKind::Annotation,
+    };
+    let mut symbols = vec![Symbol {
+        name: class.name.clone(),
+        qualified_name: qualified_name.clone(),
+        kind,
+        container: Some(class.package.clone()).filter(|package| !package.is_empty()),
+        location: Some(class.location.clone()),
+    }];
+
+    let member = |name: &str, kind: SymbolKind, location: &SourceLocation| Symbol {
+        name: name.to_string(),
+        qualified_name: format!("{}.{}", qualified_name, name),
+        kind,
+        container: Some(qualified_name.clone()),
+        location: Some(location.clone()),
+    };
+    for constant in &class.enum_constants {
+        let kind = SymbolKind::EnumConstant;
+        symbols.push(member(&constant.name, kind, &constant.location));
+    }
+    for field in &class.fields {
+        symbols.push(member(&field.name, SymbolKind::Field, &field.location));
+    }
+    for method in &class.methods {
+        symbols.push(member(&method.name, SymbolKind::Method, &method.location));
+    }
+    symbols
+}
+
+fn indexes() -> MutexGuard<'static, HashMap<u16, Arc<SymbolIndex>>> {
+    INDEXES
+        .get_or_init(|| Mutex::new(HashMap::new()))
+        .lock()
+        .unwrap_or_else(|poisoned| poisoned.into_inner())
+}
+
+/// Index of a project, built with `build` the first time it's asked for. Other projects
+/// aren't blocked while it builds, if two searches build it at once the first one is kept.
+pub fn project_index(project_id: u16, build: impl FnOnce() -> SymbolIndex) -> Arc<SymbolIndex> {
+    if let Some(index) = indexes().get(&project_id) {
+        return index.clone();
+    }
+    let index = Arc::new(build());
+    indexes().entry(project_id).or_insert(index).clone()
+}
+
+/// Apply `update` to the index of a project, if it has been built
+pub fn update_project_index(project_id: u16, update: impl FnOnce(&mut SymbolIndex)) {
+    if let Some(index) = indexes().get_mut(&project_id) {
+        // Searches still holding the old index keep their copy
+        update(Arc::make_mut(index));
+    }
+}
+
+/// Score `query` as a case-insensitive subsequence of `name`, None when it isn't one.
+/// Characters matched at word starts (`O`rder`S`ervice, after `.`, `_` or `$`) or with the same
+/// case score higher, as do runs of consecutive matches, and characters skipped between two
+/// matches cost points. Abbreviations like "OSvc" find `OrderService` before
+/// `ObjectSerializerVisitorCache`.
+fn fuzzy_score(query: &str, name: &str) -> Option<i32> {
+    const UNMATCHED: i32 = i32::MIN / 2;
+    const WORD_START: i32 = 8;
+    const SAME_CASE: i32 = 3;
+    const CONSECUTIVE: i32 = 2;
+    const SKIPPED: i32 = 2;
+    let query = query.chars().collect::<Vec<_>>();
+    let name = name.chars().collect::<Vec<_>>();
+    if query.len() > name.len() {
+        return None;
+    }
+
+    let word_start = |j: usize| {
+        j == 0
+            || matches!(name[j - 1], '.' | '_' | '$')
+            || (name[j].is_uppercase() && !name[j - 1].is_uppercase())
+            || (name[j].is_uppercase() && name.get(j + 1).is_some_and(|next| next.is_lowercase()))
+    };
+    let char_score = |i: usize, j: usize| {
+        if query[i] == name[j] {
+            1 + SAME_CASE
+        } else {
+            1
+        }
+    };
+
+    // best[j]: best score with the current query character matched at name[j], and whether
+    // the run of consecutive matches ending there began at a word start
+    let mut best = vec![(UNMATCHED, false); name.len()];
+    for (j, best) in best.iter_mut().enumerate() {
+        if query[0].to_lowercase().eq(name[j].to_lowercase()) {
+            let word = word_start(j);
+            let bonus = if word { WORD_START } else { 0 };
+            // Matches further into the name start out behind
+            *best = (char_score(0, j) + bonus - j.min(5) as i32, word);
+        }
+    }
+    for (i, query_char) in query.iter().enumerate().skip(1) {
+        let mut next = vec![(UNMATCHED, false); name.len()];
+        // best of best[..j - 1], less the characters skipped from there to j
+        let mut best_before = UNMATCHED;
+        for j in i..name.len() {
+            if j >= 2 {
+                best_before = (best_before - SKIPPED).max(best[j - 2].0 - SKIPPED);
+            }
+            if !query_char.to_lowercase().eq(name[j].to_lowercase()) {
+                continue;
+            }
+            let word = word_start(j);
+            // A run that began at a word start, like "Cache", scores as a word start throughout
+            let (run, run_from_word) = best[j - 1];
+            let consecutive = if word || run_from_word {
+                WORD_START
+            } else {
+                CONSECUTIVE
+            };
+            let jump = if word { WORD_START } else { 0 };
+            let previous = (run + consecutive, run_from_word).max((best_before + jump, word));
+            if previous.0 > UNMATCHED / 2 {
+                next[j] = (previous.0 + char_score(i, j), previous.1);
+            }
+        }
+        best = next;
+    }
+    best.into_iter()
+        .map(|(score, _)| score)
+        .max()
+        .filter(|score| *score > UNMATCHED / 2)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn symbol(qualified_name: &str, kind: SymbolKind) -> Symbol {
+        let (container, name) = qualified_name.rsplit_once('.').unwrap();
+        Symbol {
+            name: name.to_string(),
+            qualified_name: qualified_name.to_string(),
+            kind,
+            container: Some(container.to_string()),
+            location: None,
+        }
+    }
+
+    #[test]
+    fn fuzzy_score_prefers_word_starts() {
+        assert!(fuzzy_score("os", "OrderService") > fuzzy_score("os", "Host"));
+        assert_eq!(fuzzy_score("xyz", "OrderService"), None);
+        assert_eq!(fuzzy_score("OrderServices", "OrderService"), None);
+    }
+
+    #[test]
+    fn search_ranks_types_above_members() {
+        let symbols = vec![
+            symbol("com.shop.OrderService", SymbolKind::Class),
+            symbol("com.shop.OrderServiceImpl", SymbolKind::Class),
+            symbol("com.shop.ObjectSerializerVisitorCache", SymbolKind::Class),
+            symbol("com.shop.Stats.osvcCount", SymbolKind::Field),
+        ];
+        let index = SymbolIndex {
+            files: HashMap::from([("Shop.java".to_string(), symbols)]),
+            packages: Vec::new(),
+        };
+        let names = index
+            .search("OSvc", 10)
+            .into_iter()
+            .map(|symbol| symbol.name)
+            .collect::<Vec<_>>();
+        assert_eq!(
+            names,
+            [
+                "OrderService",
+                "OrderServiceImpl",
+                "ObjectSerializerVisitorCache",
+                "osvcCount"
+            ]
+        );
+    }
+
+    #[test]
+    fn fuzzy_score_prefers_contiguous_matches() {
+        let better = |query: &str, name: &str, other: &str| {
+            assert!(fuzzy_score(query, name) > fuzzy_score(query, other));
+        };
+        better("order", "OrderService", "OtherRecorder");
+        better("ordsvc", "OrderService", "OrderedSetVisitorCache");
+        better(
+            "cache",
+            "ObjectSerializerVisitorCache",
+            "CustomerAccountHelper",
+        );
+        better("OSI", "OrderServiceImpl", "OrderService");
+    }
+}
@@ -0,0 +1,372 @@
+use std::collections::{HashMap, HashSet};
+
+use crate::types::{Annotation, ClassInfo, ClassMethod, Import, Parameter, TypeArgument, TypeRef};
+
+/// Types of `java.lang`, which every file imports implicitly
+const JAVA_LANG_TYPES: [&str; 52] = [
+    "Object",
+    "String",
+    "CharSequence",
+    "StringBuilder",
+    "StringBuffer",
+    "Boolean",
+    "Byte",
+    "Character",
+    "Short",
+    "Integer",
+    "Long",
+    "Float",
+    "Double",
+    "Number",
+    "Void",
+    "Math",
+    "System",
+    "Runtime",
+    "Process",
+    "Thread",
+    "ThreadLocal",
+    "Runnable",
+    "Iterable",
+    "Comparable",
+    "Cloneable",
+    "AutoCloseable",
+    "Class",
+    "ClassLoader",
+    "Enum",
+    "Record",
+    "Throwable",
+    "Exception",
+    "Error",
+    "RuntimeException",
+    "IllegalArgumentException",
+    "IllegalStateException",
+    "NullPointerException",
+    "UnsupportedOperationException",
+    "IndexOutOfBoundsException",
+    "ArrayIndexOutOfBoundsException",
+    "ClassCastException",
+    "ArithmeticException",
+    "NumberFormatException",
+    "InterruptedException",
+    "CloneNotSupportedException",
+    "ReflectiveOperationException",
+    "AssertionError",
+    "Override",
+    "Deprecated",
+    "FunctionalInterface",
+    "SuppressWarnings",
+    "SafeVarargs",
+];
+
+/// What a simple type name can refer to inside one class
+struct Scope<'a> {
+    known_types: &'a HashSet<String>, // qualified names of every project type
+    package: &'a str,
+    imports: &'a [Import],
+    enclosing: Vec<String>, // the class and its outer classes, innermost first
+    type_variables: Vec<String>,
+}
+
+/// Resolve every type reference and annotation of the parsed classes to a fully qualified
+/// name, following Java's lookup order: type variables, member types of the class and its
+/// outer classes, single-type imports, the same package, wildcard imports and `java.lang`.
+/// `project_types` are the qualified names of the project's other types, which references
+/// can point to without being among `classes`.
+pub fn resolve_types(classes: &mut [ClassInfo], project_types: &HashSet<String>) {
+    let mut known_types = project_types.clone();
+    known_types.extend(classes.iter().map(|class| class.qualified_name()));
+    // Type parameters of outer classes are visible in their inner classes
+    let type_parameters = classes
+        .iter()
+        .map(|class| {
+            let names = class.type_parameters.iter().map(|t| t.name.clone());
+            (class.qualified_name(), names.collect::<Vec<_>>())
+        })
+        .collect::<HashMap<_, _>>();
+
+    for class in classes.iter_mut() {
+        let package = class.package.clone();
+        let imports = std::mem::take(&mut class.imports);
+
+        // "Outer.Inner" is enclosed by itself and by "Outer"
+        let mut enclosing = Vec::new();
+        let mut name = class.name.as_str();
+        loop {
+            enclosing.push(name.to_string());
+            match name.rsplit_once('.') {
+                Some((outer, _)) => name = outer,
+                None => break,
+            }
+        }
+        let type_variables = enclosing
+            .iter()
+            .filter_map(|name| type_parameters.get(&qualify(&package, name)))
+            .flatten()
+            .cloned()
+            .collect();
+
+        let mut scope = Scope {
+            known_types: &known_types,
+            package: &package,
+            imports: &imports,
+            enclosing,
+            type_variables,
+        };
+        resolve_class(class, &mut scope);
+        class.imports = imports;
+    }
+}
+
+fn resolve_class(class: &mut ClassInfo, scope: &mut Scope) {
+    resolve_annotations(&mut class.annotations, scope);
+    for type_parameter in &mut class.type_parameters {
+        for bound in &mut type_parameter.bounds {
+            resolve_type(bound, scope);
+        }
+    }
+    let supertypes = class
+        .extends
+        .iter_mut()
+        .chain(&mut class.implements)
+        .chain(&mut class.permits);
+    for supertype in supertypes {
+        resolve_type(supertype, scope);
+    }
+
+    for field in &mut class.fields {
+        resolve_type(&mut field.type_ref, scope);
+        resolve_annotations(&mut field.annotations, scope);
+    }
+    for constructor in &mut class.constructors {
+        resolve_annotations(&mut constructor.annotations, scope);
+        resolve_parameters(&mut constructor.parameters, scope);
+        for type_ref in constructor
+            .throws
+            .iter_mut()
+            .chain(&mut constructor.used_types)
+        {
+            resolve_type(type_ref, scope);
+        }
+    }
+    for method in &mut class.methods {
+        resolve_method(method, scope);
+    }
+    for constant in &mut class.enum_constants {
+        for method in &mut constant.methods {
+            resolve_method(method, scope);
+        }
+    }
+}
+
+fn resolve_method(method: &mut ClassMethod, scope: &mut Scope) {
+    // Generic methods declare type variables of their own for the signature
+    let class_variables = scope.type_variables.len();
+    let method_variables = method.type_parameters.iter().map(|t| t.name.clone());
+    scope.type_variables.extend(method_variables);
+
+    for type_parameter in &mut method.type_parameters {
+        for bound in &mut type_parameter.bounds {
+            resolve_type(bound, scope);
+        }
+    }
+    resolve_type(&mut method.return_type, scope);
+    resolve_annotations(&mut method.annotations, scope);
+    resolve_parameters(&mut method.parameters, scope);
+    for type_ref in method.throws.iter_mut().chain(&mut method.used_types) {
+        resolve_type(type_ref, scope);
+    }
+
+    scope.type_variables.truncate(class_variables);
+}
+
+fn resolve_parameters(parameters: &mut [Parameter], scope: &Scope) {
+    for parameter in parameters {
+        resolve_type(&mut parameter.type_ref, scope);
+        resolve_annotations(&mut parameter.annotations, scope);
+    }
+}
+
+fn resolve_annotations(annotations: &mut [Annotation], scope: &Scope) {
+    for annotation in annotations {
+        annotation.qualified_name = match annotation.name.rsplit_once('.') {
+            Some((qualifier, name)) => scope.resolve(Some(qualifier), name),
+            None => scope.resolve(None, &annotation.name),
+        };
+    }
+}
+
+fn resolve_type(type_ref: &mut TypeRef, scope: &Scope) {
+    if !type_ref.primitive {
+        type_ref.qualified_name = scope.resolve(type_ref.qualifier.as_deref(), &type_ref.name);
+    }
+    for argument in &mut type_ref.arguments {
+        match argument {
+            TypeArgument::Type(argument)
+            | TypeArgument::Extends(argument)
+            | TypeArgument::Super(argument) => resolve_type(argument, scope),
+            TypeArgument::Wildcard => {}
+        }
+    }
+}
+
+impl Scope<'_> {
+    /// Resolve a name as written, `qualifier` is either a package or an outer type
+    fn resolve(&self, qualifier: Option<&str>, name: &str) -> Option<String> {
+        let qualifier = match qualifier {
+            Some(qualifier) => qualifier,
+            None => return self.resolve_simple(name),
+        };
+        // Packages are lower case by convention, so `java.util.List` is already qualified
+        // while `Map.Entry` is qualified by the type `Map`
+        if qualifier.starts_with(|c: char| c.is_lowercase()) {
+            return Some(format!("{}.{}", qualifier, name));
+        }
+        let (outer, inner) = match qualifier.split_once('.') {
+            Some((outer, inner)) => (outer, Some(inner)),
+            None => (qualifier, None),
+        };
+        let outer = self.resolve_simple(outer)?;
+        match inner {
+            Some(inner) => Some(format!("{}.{}.{}", outer, inner, name)),
+            None => Some(format!("{}.{}", outer, name)),
+        }
+    }
+
+    fn resolve_simple(&self, name: &str) -> Option<String> {
+        if self.type_variables.iter().any(|variable| variable == name) {
+            return None;
+        }
+
+        for enclosing in &self.enclosing {
+            let candidate = qualify(self.package, &format!("{}.{}", enclosing, name));
+            if self.known_types.contains(&candidate) {
+                return Some(candidate);
+            }
+        }
+
+        // Static imports can import member types as well as fields and methods
+        let single_type = self.imports.iter().find(|import| {
+            !import.wildcard
+                && import.path.rsplit('.').next() == Some(name)
+                && (!import.is_static || self.known_types.contains(&import.path))
+        });
+        if let Some(import) = single_type {
+            return Some(import.path.clone());
+        }
+
+        let candidate = qualify(self.package, name);
+        if self.known_types.contains(&candidate) {
+            return Some(candidate);
+        }
+
+        for import in self.imports.iter().filter(|import| import.wildcard) {
+            let candidate = format!("{}.{}", import.path, name);
+            if self.known_types.contains(&candidate) {
+                return Some(candidate);
+            }
+        }
+
+        if JAVA_LANG_TYPES.contains(&name) {
+            return Some(format!("java.lang.{}", name));
+        }
+
+        // Without wildcard imports the only place left for the type is the same package
+        let wildcards = self
+            .imports
+            .iter()
+            .any(|import| import.wildcard && !import.is_static);
+        if wildcards {
+            None
+        } else {
+            Some(candidate)
+        }
+    }
+}
+
+fn qualify(package: &str, name: &str) -> String {
+    if package.is_empty() {
+        name.to_string()
+    } else {
+        format!("{}.{}", package, name)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashSet;
+
+    use super::resolve_types;
+    use crate::java_parser::parse_java_source;
+    use crate::types::ClassInfo;
+
+    fn resolved(files: &[&str]) -> Vec<ClassInfo> {
+        let mut classes = files
+            .iter()
+            .enumerate()
+            .flat_map(|(i, source_code)| {
+                let file_path = format!("File{}.java", i);
+                parse_java_source(&file_path, source_code).unwrap().classes
+            })
+            .collect::<Vec<_>>();
+        resolve_types(&mut classes, &HashSet::new());
+        classes
+    }
+
+    fn field_type(class: &ClassInfo, field_name: &str) -> Option<String> {
+        let field = class.fields.iter().find(|field| field.name == field_name);
+        field.unwrap().type_ref.qualified_name.clone()
+    }
+
+    #[test]
+    fn single_segment_package() {
+        let classes = resolved(&["package app; class A { B b; }", "package app; class B {}"]);
+        assert_eq!(classes[0].package, "app");
+        assert_eq!(field_type(&classes[0], "b").as_deref(), Some("app.B"));
+    }
+
+    #[test]
+    fn imports_and_java_lang() {
+        let classes = resolved(&[
+            "package app.web; import app.B; class C { B b; String name; }",
+            "package app; class B {}",
+        ]);
+        assert_eq!(field_type(&classes[0], "b").as_deref(), Some("app.B"));
+        assert_eq!(
+            field_type(&classes[0], "name").as_deref(),
+            Some("java.lang.String")
+        );
+    }
+
+    #[test]
+    fn wildcard_imports_of_project_types() {
+        let mut classes = parse_java_source(
+            "Order.java",
+            "package app.order;
+            import java.util.*;
+            import app.customer.*;
+            class Order { Customer customer; Invoice invoice; List<Line> lines; }",
+        )
+        .unwrap()
+        .classes;
+        // Only Order was parsed, the other types are known from the rest of the project
+        let project_types = ["app.customer.Customer", "app.order.Line"]
+            .map(String::from)
+            .into_iter()
+            .collect::<HashSet<_>>();
+        resolve_types(&mut classes, &project_types);
+        assert_eq!(
+            field_type(&classes[0], "customer").as_deref(),
+            Some("app.customer.Customer")
+        );
+        // A wildcard import could bring it in as well, so there is no telling where it's from
+        assert_eq!(field_type(&classes[0], "invoice"), None);
+        let lines = &classes[0].fields[2].type_ref;
+        assert_eq!(lines.qualified_name, None);
+        match &lines.arguments[0] {
+            crate::types::TypeArgument::Type(line) => {
+                assert_eq!(line.qualified_name.as_deref(), Some("app.order.Line"))
+            }
+            argument => panic!("unexpected type argument {}", argument),
+        }
+    }
+}
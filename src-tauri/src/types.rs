@@ -1,6 +1,10 @@
+use std::collections::HashMap;
+
 use serde::{Deserialize, Serialize};
 
-#[derive(serde::Serialize, serde::Deserialize, Debug)]
+use crate::diagram_generator::Relationship;
+
+#[derive(serde::Serialize, serde::Deserialize, Debug, Clone)]
 pub struct FileNode {
     pub name: String,
     pub path: String,
@@ -16,37 +20,361 @@ pub struct Project {
     pub last_opened: u128,
 }
 
+/// Content of the config file, projects.json
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+pub struct Config {
+    pub projects: Vec<Project>,
+    #[serde(default)]
+    pub settings: Settings,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+#[serde(default)]
+pub struct Settings {
+    pub editor: EditorCommand, // opens diagram elements at their source
+}
+
+/// A program and its arguments, in which `{file}`, `{line}` and `{column}` are replaced with
+/// the location to open. Arguments are never split, so paths with spaces stay whole.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct EditorCommand {
+    pub program: String,
+    pub args: Vec<String>,
+}
+
+impl Default for EditorCommand {
+    fn default() -> Self {
+        EditorCommand {
+            program: String::from("code"),
+            args: vec![
+                String::from("--goto"),
+                String::from("{file}:{line}:{column}"),
+            ],
+        }
+    }
+}
+
+impl EditorCommand {
+    pub fn arguments(&self, location: &SourceLocation) -> Vec<String> {
+        self.args
+            .iter()
+            .map(|argument| {
+                argument
+                    .replace("{file}", &location.file)
+                    .replace("{line}", &location.start_line.to_string())
+                    .replace("{column}", &location.start_column.to_string())
+            })
+            .collect()
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Default, PartialEq)]
+pub struct TypeRef {
+    pub name: String,              // simple name, e.g. "List" or "int"
+    pub qualifier: Option<String>, // package or outer type, e.g. "java.util" or "Map"
+    pub arguments: Vec<TypeArgument>,
+    pub array_dimensions: usize,
+    pub varargs: bool,
+    pub primitive: bool,
+    pub qualified_name: Option<String>, // e.g. "java.util.List", once resolved against the imports
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub enum TypeArgument {
+    Type(TypeRef),
+    Wildcard,         // ?
+    Extends(TypeRef), // ? extends T
+    Super(TypeRef),   // ? super T
+}
+
+impl std::fmt::Display for TypeRef {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if let Some(qualifier) = &self.qualifier {
+            write!(f, "{}.", qualifier)?;
+        }
+        write!(f, "{}", self.name)?;
+        if !self.arguments.is_empty() {
+            let arguments = self
+                .arguments
+                .iter()
+                .map(|argument| argument.to_string())
+                .collect::<Vec<_>>()
+                .join(", ");
+            write!(f, "<{}>", arguments)?;
+        }
+        let dimensions = if self.varargs {
+            self.array_dimensions.saturating_sub(1)
+        } else {
+            self.array_dimensions
+        };
+        write!(f, "{}", "[]".repeat(dimensions))?;
+        if self.varargs {
+            write!(f, "...")?;
+        }
+        Ok(())
+    }
+}
+
+impl std::fmt::Display for TypeArgument {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            TypeArgument::Type(type_ref) => write!(f, "{}", type_ref),
+            TypeArgument::Wildcard => write!(f, "?"),
+            TypeArgument::Extends(bound) => write!(f, "? extends {}", bound),
+            TypeArgument::Super(bound) => write!(f, "? super {}", bound),
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct TypeParameter {
+    pub name: String,
+    pub bounds: Vec<TypeRef>, // T extends A & B
+}
+
+impl std::fmt::Display for TypeParameter {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.name)?;
+        if !self.bounds.is_empty() {
+            let bounds = self
+                .bounds
+                .iter()
+                .map(|bound| bound.to_string())
+                .collect::<Vec<_>>()
+                .join(" & ");
+            write!(f, " extends {}", bounds)?;
+        }
+        Ok(())
+    }
+}
+
+/// Where an element is declared in its source file, lines and columns start at 1
+#[derive(Debug, Serialize, Deserialize, Clone, Default, PartialEq)]
+pub struct SourceLocation {
+    pub file: String,
+    pub start_byte: usize,
+    pub end_byte: usize,
+    pub start_line: usize,
+    pub start_column: usize,
+    pub end_line: usize,
+    pub end_column: usize,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq)]
+pub enum Severity {
+    Error,
+    Warning,
+}
+
+/// A problem found while parsing a file, lines and columns start at 1
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct Diagnostic {
+    pub file: String,
+    pub line: usize,
+    pub column: usize,
+    pub severity: Severity,
+    pub message: String,
+}
+
+/// Classes of one file, parsed as far as its syntax errors allow
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct ParsedFile {
+    pub classes: Vec<ClassInfo>,
+    pub diagnostics: Vec<Diagnostic>,
+}
+
+/// Payload of the `project-changed` event, sent when files of a watched project change
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct ProjectChange {
+    pub project_id: u16,
+    pub changed_files: Vec<String>, // parsed again
+    pub removed_files: Vec<String>,
+    pub structure: Option<FileNode>, // the new file tree, when files were added or removed
+}
+
+/// What the diagram command returns to the frontend
 #[derive(Debug, Serialize, Deserialize)]
+pub struct GeneratedDiagram {
+    pub mermaid: String,
+    pub relationships: Vec<Relationship>, // the edges of the diagram, for tooltips
+    pub locations: HashMap<String, NodeLocation>, // by the name a class is shown with
+    pub diagnostics: Vec<Diagnostic>,
+}
+
+/// Where a class of the diagram and its members are declared, to open them in an editor
+#[derive(Debug, Serialize, Deserialize)]
+pub struct NodeLocation {
+    pub class: SourceLocation,
+    pub members: Vec<MemberLocation>, // in the order the diagram lists them
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct MemberLocation {
+    pub name: String, // constructors are named after the class
+    pub location: SourceLocation,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct Annotation {
+    pub name: String, // as written, e.g. "Entity" or "javax.persistence.Entity"
+    pub qualified_name: Option<String>, // when it can be resolved from the source file
+    pub elements: Vec<AnnotationElement>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct AnnotationElement {
+    pub name: String,  // "value" for the single element shorthand @Table("orders")
+    pub value: String, // as written, e.g. "\"orders\"" or "{\"a\", \"b\"}"
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct Import {
+    pub path: String, // e.g. "java.util.List", or "java.util" for a wildcard import
+    pub is_static: bool,
+    pub wildcard: bool,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Default)]
+pub enum Visibility {
+    Public,
+    Protected,
+    #[default]
+    PackagePrivate,
+    Private,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq)]
+pub enum Modifier {
+    Static,
+    Final,
+    Abstract,
+    Default,
+    Synchronized,
+    Native,
+    Transient,
+    Volatile,
+    Sealed,
+    NonSealed,
+}
+
+/// Where a field gets a value from, as far as its own class shows
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq)]
+pub enum FieldValue {
+    Created,              // a `new` expression, in the declaration or any member
+    ConstructorParameter, // e.g. `this.repo = repo` in a constructor
+    MethodParameter,      // e.g. `this.repo = repo` in a setter
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct ClassField {
     pub name: String,
-    pub type_name: String,
-    pub visibility: String,
+    pub type_ref: TypeRef,
+    pub visibility: Visibility,
+    pub modifiers: Vec<Modifier>,
+    pub annotations: Vec<Annotation>,
+    pub values: Vec<FieldValue>,
+    pub location: SourceLocation,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct Parameter {
+    pub name: String,
+    pub type_ref: TypeRef,
+    pub annotations: Vec<Annotation>,
+    pub location: SourceLocation,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct ClassMethod {
     pub name: String,
-    pub return_type: String,
-    pub visibility: String,
-    pub parameters: Vec<(String, String)>, // (param_name, param_type)
+    pub return_type: TypeRef,
+    pub visibility: Visibility,
+    pub modifiers: Vec<Modifier>,
+    pub type_parameters: Vec<TypeParameter>,
+    pub parameters: Vec<Parameter>,
+    pub throws: Vec<TypeRef>,
+    pub used_types: Vec<TypeRef>, // see ClassConstructor
+    pub annotations: Vec<Annotation>,
+    pub location: SourceLocation,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct ClassConstructor {
+    pub visibility: Visibility,
+    pub parameters: Vec<Parameter>,
+    pub throws: Vec<TypeRef>,
+    // Types the body declares variables of, instantiates or calls static methods on, once each
+    pub used_types: Vec<TypeRef>,
+    pub annotations: Vec<Annotation>,
+    pub location: SourceLocation,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct EnumConstant {
+    pub name: String,
+    pub arguments: Vec<String>, // constructor arguments as written, e.g. "\"€\""
+    pub methods: Vec<ClassMethod>, // methods of a constant-specific body
+    pub location: SourceLocation,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
 pub enum ClassType {
     Class,
     AbstractClass,
     Interface,
     Enum,
     Record,
+    Annotation,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct ClassInfo {
     pub name: String,
     pub package: String,
+    pub enum_constants: Vec<EnumConstant>,
     pub fields: Vec<ClassField>,
+    pub constructors: Vec<ClassConstructor>,
     pub methods: Vec<ClassMethod>,
-    pub extends: Option<String>,
-    pub implements: Vec<String>,
+    pub type_parameters: Vec<TypeParameter>,
+    pub extends: Option<TypeRef>,
+    pub implements: Vec<TypeRef>, // for interfaces, the extended interfaces
+    pub outer: Option<String>,    // enclosing type name for nested declarations
+    pub modifiers: Vec<Modifier>,
+    pub permits: Vec<TypeRef>, // permitted subtypes of a sealed type
+    pub annotations: Vec<Annotation>,
+    pub imports: Vec<Import>, // imports of the declaring file
+    pub location: SourceLocation,
     pub class_type: ClassType,
 }
+
+impl ClassInfo {
+    pub fn new(class_type: ClassType) -> Self {
+        ClassInfo {
+            name: String::new(),
+            package: String::new(),
+            enum_constants: Vec::new(),
+            fields: Vec::new(),
+            constructors: Vec::new(),
+            methods: Vec::new(),
+            type_parameters: Vec::new(),
+            extends: None,
+            implements: Vec::new(),
+            outer: None,
+            modifiers: Vec::new(),
+            permits: Vec::new(),
+            annotations: Vec::new(),
+            imports: Vec::new(),
+            location: SourceLocation::default(),
+            class_type,
+        }
+    }
+
+    /// Package-qualified name, e.g. `com.example.Outer.Inner`
+    pub fn qualified_name(&self) -> String {
+        if self.package.is_empty() {
+            self.name.clone()
+        } else {
+            format!("{}.{}", self.package, self.name)
+        }
+    }
+}
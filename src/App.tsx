@@ -1,6 +1,7 @@
 import { Button } from "@/components/ui/button";
 import { PanzoomObject } from "@panzoom/panzoom";
 import { invoke } from "@tauri-apps/api/core";
+import { listen } from "@tauri-apps/api/event";
 import { writeImage } from "@tauri-apps/plugin-clipboard-manager";
 import { info } from "@tauri-apps/plugin-log";
 import { Buffer } from "buffer";
@@ -19,35 +20,96 @@ import {
   DialogTitle,
   DialogTrigger,
 } from "./components/ui/dialog";
+import { Input } from "./components/ui/input";
 import { Switch } from "./components/ui/switch";
 import { Tabs, TabsContent, TabsList, TabsTrigger } from "./components/ui/tabs";
-import { useFileStore } from "./hooks/store";
+import { useFileStore, useOptionsStore } from "./hooks/store";
 import Layout from "./layout";
+import {
+  Diagnostic,
+  GeneratedDiagram,
+  NodeLocation,
+  ProjectChange,
+  Settings,
+} from "./types/types";
 
 function App() {
   const { selectedFiles } = useFileStore();
+  const { options } = useOptionsStore();
   const [mermaid, setMermaid] = useState("");
+  const [diagnostics, setDiagnostics] = useState<Diagnostic[]>([]);
+  const [locations, setLocations] = useState<Record<string, NodeLocation>>(
+    {}
+  );
   const [vertical, setVertical] = useState(false);
   const [control, setControl] = useState<PanzoomObject | null>(null);
+  // bumped when selected files change on disk, to generate the diagram again
+  const [revision, setRevision] = useState(0);
+  const [settings, setSettings] = useState<Settings | null>(null);
+  // editor arguments, one per line so arguments with spaces stay whole
+  const [editorArgs, setEditorArgs] = useState("");
+
+  useEffect(() => {
+    invoke<Settings>("get_settings")
+      .then((settings) => {
+        setSettings(settings);
+        setEditorArgs(settings.editor.args.join("\n"));
+      })
+      .catch((e) => {
+        info(e);
+      });
+  }, []);
+
+  const saveSettings = () => {
+    if (!settings) {
+      return;
+    }
+    const args = editorArgs.split("\n").filter((arg) => arg.trim() !== "");
+    const updated = { ...settings, editor: { ...settings.editor, args } };
+    invoke("save_settings", { settings: updated })
+      .then(() => setSettings(updated))
+      .catch((e) => {
+        info(e);
+      });
+  };
+
+  useEffect(() => {
+    const unlisten = listen<ProjectChange>("project-changed", (event) => {
+      const { changed_files, removed_files } = event.payload;
+      const selected = new Set(useFileStore.getState().selectedFiles);
+      const files = [...changed_files, ...removed_files];
+      if (files.some((file) => selected.has(file))) {
+        setRevision((revision) => revision + 1);
+      }
+    });
+    return () => {
+      unlisten.then((stop) => stop());
+    };
+  }, []);
 
   useEffect(() => {
     // info("Generating mermaid class diagram");
     if (selectedFiles.length === 0) {
       setMermaid("");
+      setDiagnostics([]);
+      setLocations({});
       return;
     }
-    invoke("generate_mermaid_class_diagram", {
+    invoke<GeneratedDiagram>("generate_mermaid_class_diagram", {
       filePaths: Array.from(selectedFiles),
       vertical,
+      options,
     })
       .then((res) => {
         // info(JSON.stringify(res, null, 2));
-        setMermaid(res as string);
+        setMermaid(res.mermaid);
+        setDiagnostics(res.diagnostics);
+        setLocations(res.locations);
       })
       .catch((e) => {
         info(e);
       });
-  }, [selectedFiles, vertical]);
+  }, [selectedFiles, vertical, options, revision]);
 
   const handleExportImage = async () => {
     // const state: State = {
@@ -79,6 +141,21 @@ function App() {
     };
   };
 
+  // open a class, or one of its members when the click was on its line
+  const openClass = (name: string, member?: number) => {
+    const location = locations[name];
+    if (!location) {
+      return;
+    }
+    const target =
+      member === undefined ? location.class : location.members[member]?.location;
+    invoke("open_in_editor", { location: target ?? location.class }).catch(
+      (e) => {
+        info(e);
+      }
+    );
+  };
+
   const resetZoom = () => {
     control?.reset();
   };
@@ -108,11 +185,43 @@ function App() {
                   <DialogHeader>
                     <DialogTitle>Settings</DialogTitle>
                   </DialogHeader>
-                  <div className="">
+                  <div className="flex flex-col gap-4">
                     <div className="flex justify-between">
                       <p>Dark mode</p>
                       <ModeToggle />
                     </div>
+                    {settings && (
+                      <div className="flex flex-col gap-2">
+                        <label htmlFor="editor-program">Editor</label>
+                        <Input
+                          id="editor-program"
+                          value={settings.editor.program}
+                          onChange={(e) =>
+                            setSettings({
+                              ...settings,
+                              editor: {
+                                ...settings.editor,
+                                program: e.target.value,
+                              },
+                            })
+                          }
+                        />
+                        <label htmlFor="editor-args">
+                          Arguments, one per line with {"{file}"}, {"{line}"}{" "}
+                          and {"{column}"}
+                        </label>
+                        <textarea
+                          id="editor-args"
+                          rows={3}
+                          value={editorArgs}
+                          onChange={(e) => setEditorArgs(e.target.value)}
+                          className="border-input dark:bg-input/30 rounded-md border bg-transparent px-3 py-1 font-mono text-sm"
+                        />
+                        <Button size="sm" onClick={saveSettings}>
+                          Save
+                        </Button>
+                      </div>
+                    )}
                   </div>
                 </DialogContent>
               </Dialog>
@@ -124,6 +233,9 @@ function App() {
                 <TabsList className="w-full">
                   <TabsTrigger value="diagram">Diagram</TabsTrigger>
                   <TabsTrigger value="source">Source</TabsTrigger>
+                  <TabsTrigger value="problems">
+                    Problems ({diagnostics.length})
+                  </TabsTrigger>
                 </TabsList>
                 <TabsContent value="diagram">
                   <div className="flex justify-between items-center">
@@ -172,6 +284,8 @@ function App() {
                       chart={mermaid}
                       id={"randomId"}
                       onChange={setControl}
+                      nodeNames={Object.keys(locations)}
+                      onNodeClick={openClass}
                     />
                   </div>
                 </TabsContent>
@@ -187,6 +301,36 @@ function App() {
                     </div>
                   </div>
                 </TabsContent>
+                <TabsContent value="problems">
+                  <div className="border rounded w-full">
+                    {diagnostics.length === 0 ? (
+                      <p className="p-4 text-muted-foreground">
+                        No problems found in the selected files
+                      </p>
+                    ) : (
+                      <ul className="divide-y text-sm">
+                        {diagnostics.map((diagnostic, index) => (
+                          <li key={index} className="flex gap-2 p-2">
+                            <span
+                              className={
+                                diagnostic.severity === "Error"
+                                  ? "text-destructive"
+                                  : "text-yellow-600"
+                              }
+                            >
+                              {diagnostic.severity}
+                            </span>
+                            <span className="font-mono">
+                              {diagnostic.file}:{diagnostic.line}:
+                              {diagnostic.column}
+                            </span>
+                            <span>{diagnostic.message}</span>
+                          </li>
+                        ))}
+                      </ul>
+                    )}
+                  </div>
+                </TabsContent>
               </Tabs>
             ) : (
               <div className="flex items-center justify-center h-full text-muted-foreground">
@@ -9,16 +9,20 @@ import {
 import {
   Sidebar,
   SidebarContent,
+  SidebarFooter,
+  SidebarGroupLabel,
   SidebarHeader,
   SidebarRail,
 } from "@/components/ui/sidebar";
 import { useFileStore } from "@/hooks/store";
 import { search } from "@/lib/search";
-import { Project } from "@/types/types";
+import { Project, ProjectChange } from "@/types/types";
 import { invoke } from "@tauri-apps/api/core";
+import { listen } from "@tauri-apps/api/event";
 import { open } from "@tauri-apps/plugin-dialog";
 import { Plus, XIcon } from "lucide-react";
 import { useEffect, useMemo, useState } from "react";
+import { DiagramOptionsPanel } from "./diagram-options";
 import { FileNode, FileTreeNode } from "./file-tree";
 import { Input } from "./ui/input";
 
@@ -66,6 +70,37 @@ export function AppSidebar() {
     fetchStructure();
   }, [selectedProject]);
 
+  useEffect(() => {
+    if (!selectedProject) {
+      return;
+    }
+    // Files were added or removed on disk, keep what is still there selected
+    const markSelected = (
+      nodes: FileNode[],
+      selected: Set<string>
+    ): FileNode[] =>
+      nodes.map((node) => ({
+        ...node,
+        selected: selected.has(node.path),
+        children: node.children
+          ? markSelected(node.children, selected)
+          : undefined,
+      }));
+    const unlisten = listen<ProjectChange>("project-changed", (event) => {
+      const { project_id, structure } = event.payload;
+      if (project_id.toString() !== selectedProject || !structure) {
+        return;
+      }
+      const selected = new Set(useFileStore.getState().selectedFiles);
+      const files = markSelected([structure], selected);
+      setFiles(files);
+      setFileStore(files);
+    });
+    return () => {
+      unlisten.then((stop) => stop());
+    };
+  }, [selectedProject]);
+
   const handleAddProject = async () => {
     try {
       const selected = await open({
@@ -212,6 +247,10 @@ export function AppSidebar() {
             ))}
         </div>
       </SidebarContent>
+      <SidebarFooter className="border-t">
+        <SidebarGroupLabel>Diagram options</SidebarGroupLabel>
+        <DiagramOptionsPanel />
+      </SidebarFooter>
       <SidebarRail />
     </Sidebar>
   );
@@ -0,0 +1,112 @@
+import { Input } from "@/components/ui/input";
+import { Switch } from "@/components/ui/switch";
+import { useOptionsStore } from "@/hooks/store";
+import { DiagramOptions } from "@/types/types";
+import { useState } from "react";
+
+type BooleanOption = {
+  [K in keyof DiagramOptions]-?: DiagramOptions[K] extends boolean | undefined
+    ? K
+    : never;
+}[keyof DiagramOptions];
+
+function OptionSwitch({
+  option,
+  label,
+}: {
+  option: BooleanOption;
+  label: string;
+}) {
+  const { options, setOptions } = useOptionsStore();
+  return (
+    <div className="flex items-center justify-between">
+      <label htmlFor={option}>{label}</label>
+      <Switch
+        id={option}
+        checked={options[option] ?? false}
+        onCheckedChange={(checked) => setOptions({ [option]: checked })}
+      />
+    </div>
+  );
+}
+
+// Applied when the input loses focus, so typing doesn't redraw the diagram
+function OptionInput({
+  label,
+  initial,
+  placeholder,
+  type = "text",
+  onApply,
+}: {
+  label: string;
+  initial: string;
+  placeholder?: string;
+  type?: "text" | "number";
+  onApply: (text: string) => void;
+}) {
+  const [text, setText] = useState(initial);
+  return (
+    <div className="flex flex-col gap-1">
+      <label>{label}</label>
+      <Input
+        type={type}
+        value={text}
+        placeholder={placeholder}
+        onChange={(e) => setText(e.target.value)}
+        onBlur={() => onApply(text.trim())}
+        onKeyDown={(e) => e.key === "Enter" && onApply(text.trim())}
+      />
+    </div>
+  );
+}
+
+const packageList = (text: string) =>
+  text
+    .split(",")
+    .map((name) => name.trim())
+    .filter((name) => name !== "");
+
+export function DiagramOptionsPanel() {
+  const { options, setOptions } = useOptionsStore();
+  return (
+    <div className="flex flex-col gap-2 text-sm">
+      <OptionSwitch option="show_record_accessors" label="Record accessors" />
+      <OptionSwitch option="show_annotations" label="Annotations" />
+      <OptionSwitch option="show_dependencies" label="Dependencies" />
+      <OptionSwitch option="show_ghost_types" label="Types outside the diagram" />
+      {options.show_ghost_types && (
+        <OptionInput
+          label="Except packages starting with"
+          initial={(options.ghost_ignored_packages ?? ["java."]).join(", ")}
+          onApply={(text) =>
+            setOptions({ ghost_ignored_packages: packageList(text) })
+          }
+        />
+      )}
+      <OptionSwitch option="group_by_package" label="Group by package" />
+      {options.group_by_package && (
+        <>
+          <OptionInput
+            label="Package prefix to leave out"
+            initial={options.namespace_prefix ?? ""}
+            placeholder="Shared by all classes"
+            onApply={(text) =>
+              // Left empty, the backend trims the package all classes share
+              setOptions({ namespace_prefix: text || undefined })
+            }
+          />
+          <OptionInput
+            label="Package depth, 0 for all"
+            type="number"
+            initial={(options.namespace_depth ?? 0).toString()}
+            onApply={(text) =>
+              setOptions({
+                namespace_depth: Math.max(0, Math.floor(Number(text)) || 0),
+              })
+            }
+          />
+        </>
+      )}
+    </div>
+  );
+}
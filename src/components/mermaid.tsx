@@ -4,14 +4,28 @@ import Panzoom, { PanzoomObject } from "@panzoom/panzoom";
 
 mermaid.initialize({});
 
+// Mermaid ids class nodes like "classId-Order.Line-3", find which of the names it is
+const nodeName = (nodeId: string, names: string[]) =>
+  names
+    .filter((name) => nodeId.includes(`-${name}-`))
+    .sort((a, b) => b.length - a.length)[0];
+
+// Field lines come before method lines, like the members of NodeLocation
+const memberLines = ".members-group > g, .methods-group > g";
+
 const Mermaid = ({
   chart,
   id,
   onChange,
+  nodeNames = [],
+  onNodeClick,
 }: {
   chart: string;
   id: string;
   onChange?: (value: PanzoomObject) => void;
+  nodeNames?: string[];
+  // member is the index of the clicked member line, in the order the class lists them
+  onNodeClick?: (name: string, member?: number) => void;
 }) => {
   useEffect(() => {
     document.getElementById(id)?.removeAttribute("data-processed");
@@ -21,6 +35,18 @@ const Mermaid = ({
       if (container && container.childElementCount === 0) {
         container.innerHTML = res.svg;
         const svg = container.querySelector("svg");
+        container.querySelectorAll<SVGGElement>("g.node").forEach((node) => {
+          const name = nodeName(node.id, nodeNames);
+          if (name && onNodeClick) {
+            node.style.cursor = "pointer";
+            node.addEventListener("click", (event) => {
+              const lines = Array.from(node.querySelectorAll(memberLines));
+              const line = (event.target as Element).closest(memberLines);
+              const member = line ? lines.indexOf(line) : -1;
+              onNodeClick(name, member >= 0 ? member : undefined);
+            });
+          }
+        });
         if (svg) {
           const panzoom = Panzoom(svg);
           onChange?.(panzoom);
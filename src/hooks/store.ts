@@ -1,4 +1,5 @@
 import { FileNode } from "@/components/file-tree";
+import { DiagramOptions } from "@/types/types";
 import { create } from "zustand";
 
 interface FileStore {
@@ -31,3 +32,15 @@ export const useFileStore = create<FileStore>((set) => ({
     set({ files: [], selectedFiles: [] });
   },
 }));
+
+interface OptionsStore {
+  options: DiagramOptions;
+  setOptions: (options: Partial<DiagramOptions>) => void;
+}
+
+export const useOptionsStore = create<OptionsStore>((set) => ({
+  options: {},
+  setOptions: (options) => {
+    set((state) => ({ options: { ...state.options, ...options } }));
+  },
+}));
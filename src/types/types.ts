@@ -7,6 +7,90 @@ export interface Project {
   last_opened: number;
 }
 
+export interface EditorCommand {
+  program: string;
+  args: string[]; // {file}, {line} and {column} are replaced with the location
+}
+
+export interface Settings {
+  editor: EditorCommand;
+}
+
+export interface Diagnostic {
+  file: string;
+  line: number;
+  column: number;
+  severity: "Error" | "Warning";
+  message: string;
+}
+
+export interface SourceLocation {
+  file: string;
+  start_byte: number;
+  end_byte: number;
+  start_line: number;
+  start_column: number;
+  end_line: number;
+  end_column: number;
+}
+
+export type RelationshipKind =
+  | "Extends"
+  | "Implements"
+  | "Association"
+  | "Composition"
+  | "Aggregation"
+  | "Dependency"
+  | "Nested";
+
+export interface Relationship {
+  from: string;
+  to: string;
+  kind: RelationshipKind;
+  label: string | null;
+  multiplicity: string | null;
+  from_multiplicity: string | null;
+  bidirectional: boolean;
+  location: SourceLocation | null;
+  reason: string | null; // why a field is a composition or aggregation
+}
+
+export interface MemberLocation {
+  name: string;
+  location: SourceLocation;
+}
+
+export interface NodeLocation {
+  class: SourceLocation;
+  members: MemberLocation[];
+}
+
+export interface GeneratedDiagram {
+  mermaid: string;
+  relationships: Relationship[];
+  locations: Record<string, NodeLocation>; // by the name a class is shown with
+  diagnostics: Diagnostic[];
+}
+
+// Options of the diagram commands, left out fields keep their default
+export interface DiagramOptions {
+  show_record_accessors?: boolean;
+  show_annotations?: boolean;
+  show_dependencies?: boolean; // types methods and constructors use
+  show_ghost_types?: boolean;
+  ghost_ignored_packages?: string[]; // package prefixes, java. by default
+  group_by_package?: boolean;
+  namespace_prefix?: string; // by default the package all classes share
+  namespace_depth?: number; // package segments after the prefix, 0 for all
+}
+
+export interface ProjectChange {
+  project_id: number;
+  changed_files: string[];
+  removed_files: string[];
+  structure: FileNode | null;
+}
+
 export const sampleStructure: FileNode = {
   name: "src",
   path: "/src",